@@ -0,0 +1,63 @@
+// Benchmarks for the gcd implementations, run with cargo bench.
+// Each input set is run through every implementation, and we print the average time per call so they can be compared side by side.
+use std::hint::black_box;
+use std::time::Instant;
+
+use hello::{binary_gcd, gcd, gcd_recursive};
+
+// fn(u64, u64) -> u64 is a function pointer type, letting us keep different functions with the same signature in one array.
+type GcdFn = fn(u64, u64) -> u64;
+
+// How many times each input set is run through an implementation. Enough to smooth out timer noise without taking too long.
+const ROUNDS: u32 = 1_000;
+
+fn main() {
+    let sets = [
+        ("small", small_pairs()),
+        ("fibonacci", fibonacci_pairs()),
+        ("random", random_pairs()),
+    ];
+
+    // gcd_recursive is the same algorithm as gcd written as recursion, so the difference between those two rows is what the recursion itself costs.
+    let implementations: [(&str, GcdFn); 3] = [("gcd", gcd), ("gcd_recursive", gcd_recursive), ("binary_gcd", binary_gcd)];
+
+    for (set_name, pairs) in &sets {
+        for (name, f) in &implementations {
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                for &(n, m) in pairs {
+                    // black_box stops the optimizer from noticing the results are never used and skipping the work.
+                    black_box(f(black_box(n), black_box(m)));
+                }
+            }
+            let calls = ROUNDS as u128 * pairs.len() as u128;
+            println!("{:<10} {:<14} {:>8} ns/call", set_name, name, start.elapsed().as_nanos() / calls);
+        }
+    }
+}
+
+fn small_pairs() -> Vec<(u64, u64)> {
+    (1..=30).flat_map(|n| (1..=30).map(move |m| (n, m))).collect()
+}
+
+// Neighbouring Fibonacci numbers take the most steps of any inputs their size, so they're the worst case for the Euclidean algorithm.
+fn fibonacci_pairs() -> Vec<(u64, u64)> {
+    let mut pairs = Vec::new();
+    let (mut a, mut b) = (1u64, 2u64);
+    while let Some(next) = a.checked_add(b) {
+        pairs.push((a, b));
+        a = b;
+        b = next;
+    }
+    pairs
+}
+
+// A fixed-seed linear congruential generator, so every run times the same "random" numbers.
+fn random_pairs() -> Vec<(u64, u64)> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        state | 1
+    };
+    (0..1_000).map(|_| (next(), next())).collect()
+}
@@ -0,0 +1,69 @@
+// Reading CSV Files
+
+// Splits CSV text into records, each a list of fields. Fields are separated by commas and records by newlines (\r\n works too).
+// A field can be wrapped in double quotes, and then it may contain commas, newlines, or a doubled "" standing for one quote, as in "say ""hi"", then go".
+// Blank lines are skipped. The only thing that can go wrong is a quote that's never closed.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    // peekable lets us look at the character after a quote without using it up, to tell "" apart from a closing quote.
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            // std::mem::take hands back the field and leaves an empty String in its place, ready for the next one.
+            ',' => record.push(std::mem::take(&mut field)),
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                push_record(&mut records, std::mem::take(&mut record));
+            }
+            '\r' => {}
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("a quoted field is never closed".to_string());
+    }
+    // The last line may not end with a newline.
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        push_record(&mut records, record);
+    }
+    Ok(records)
+}
+
+// A blank line comes out as a record with one empty field, which is dropped rather than treated as data.
+fn push_record(records: &mut Vec<Vec<String>>, record: Vec<String>) {
+    if record.len() != 1 || !record[0].is_empty() {
+        records.push(record);
+    }
+}
+
+#[test]
+fn test_parse_csv() {
+    let records = parse_csv("a,b,c\n1,2,3\r\n\n4,\"5,6\",\"say \"\"hi\"\"\"\n\"multi\nline\",,8").unwrap();
+    assert_eq!(
+        records,
+        [
+            vec!["a", "b", "c"],
+            vec!["1", "2", "3"],
+            vec!["4", "5,6", "say \"hi\""],
+            vec!["multi\nline", "", "8"],
+        ]
+    );
+    assert_eq!(parse_csv("").unwrap().len(), 0);
+    assert!(parse_csv("1,\"2\n").is_err());
+}
@@ -0,0 +1,165 @@
+// Fractions
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::reduce_fraction;
+
+// A signed fraction, always kept in lowest terms with the sign on the numerator and a positive denominator.
+// Because every value has exactly one way of being stored, two fractions are equal exactly when their fields are, so the derived PartialEq, Eq and Hash
+// all work on the reduced form: 2/4 and 1/2 are stored the same way, compare equal and hash the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fraction {
+    num: i64,
+    den: i64,
+}
+
+impl Fraction {
+    // Builds num/den in lowest terms: Fraction::new(6, -8) is -3/4. A zero denominator panics, the same as dividing by zero does.
+    // The work is done on the sizes of num and den as u64s, so even i64::MIN can be reduced. The only fraction that doesn't fit afterwards is i64::MIN/-1, which is 2^63, and that panics too.
+    pub fn new(num: i64, den: i64) -> Fraction {
+        assert!(den != 0, "a fraction can't have a zero denominator");
+        let (n, d) = reduce_fraction(num.unsigned_abs(), den.unsigned_abs());
+        let n = if (num < 0) != (den < 0) { -(n as i128) } else { n as i128 };
+        Fraction {
+            num: i64::try_from(n).expect("fraction is too large for an i64 numerator"),
+            den: d as i64,
+        }
+    }
+
+    pub fn numer(&self) -> i64 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i64 {
+        self.den
+    }
+}
+
+// Fraction::from(5) is 5/1. Numerators are i64s, so a u64 above i64::MAX doesn't fit and panics.
+impl From<u64> for Fraction {
+    fn from(n: u64) -> Fraction {
+        Fraction::new(i64::try_from(n).expect("number is too large for a Fraction"), 1)
+    }
+}
+
+// Fraction::from((6, 8)) is 3/4, going through new so it's reduced and the sign ends up on the numerator.
+impl From<(i64, i64)> for Fraction {
+    fn from((num, den): (i64, i64)) -> Fraction {
+        Fraction::new(num, den)
+    }
+}
+
+// Prints a fraction as 3/4, or -3/4 when it's negative.
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+// The fields can't just be compared in order (1/2 would sort after 2/3 by denominator), so fractions are ordered by value:
+// a/b < c/d exactly when a*d < c*b, since both denominators are positive. The products of two i64s fit in an i128, so this never overflows.
+impl Ord for Fraction {
+    fn cmp(&self, other: &Fraction) -> Ordering {
+        (self.num as i128 * other.den as i128).cmp(&(other.num as i128 * self.den as i128))
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Fraction) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Continued Fractions
+
+// Writes num/den as a continued fraction a0 + 1/(a1 + 1/(a2 + ...)), returning the terms [a0, a1, a2, ...]: 45/16 = 2 + 1/(1 + 1/(4 + 1/3)) gives [2, 1, 4, 3].
+// The terms are exactly the quotients the Euclidean algorithm finds on the way to gcd(num, den), so this is the same loop, keeping the quotients instead of the remainders.
+pub fn continued_fraction(mut num: u64, mut den: u64) -> Vec<u64> {
+    assert!(den != 0, "a fraction can't have a zero denominator");
+    let mut terms = Vec::new();
+    while den != 0 {
+        terms.push(num / den);
+        let r = num % den;
+        num = den;
+        den = r;
+    }
+    terms
+}
+
+// The other direction: works a continued fraction back out into a Fraction, from the innermost term outwards.
+// Each step turns h/k into a + 1/(h/k) = (a*h + k)/h. The numbers grow quickly, so they're kept in i128 and a result too big for a Fraction panics.
+pub fn from_continued_fraction(terms: &[u64]) -> Fraction {
+    let (last, rest) = terms.split_last().expect("a continued fraction needs at least one term");
+    let mut h = *last as i128;
+    let mut k: i128 = 1;
+    for a in rest.iter().rev() {
+        let next = (*a as i128).checked_mul(h).and_then(|ah| ah.checked_add(k)).expect("continued fraction is too large");
+        k = h;
+        h = next;
+    }
+    let overflow = |_| panic!("continued fraction is too large for a Fraction");
+    Fraction::new(i64::try_from(h).unwrap_or_else(overflow), i64::try_from(k).unwrap_or_else(overflow))
+}
+
+#[test]
+fn test_fraction_new() {
+    assert_eq!(Fraction::new(6, 8).to_string(), "3/4");
+    assert_eq!(Fraction::new(6, -8).to_string(), "-3/4");
+    assert_eq!(Fraction::new(-6, -8).to_string(), "3/4");
+    assert_eq!(Fraction::new(0, -5).to_string(), "0/1");
+    assert_eq!(Fraction::new(i64::MIN, 2).to_string(), "-4611686018427387904/1");
+}
+
+#[test]
+fn test_fraction_from() {
+    assert_eq!(Fraction::from(5), Fraction::new(5, 1));
+    assert_eq!(Fraction::from(0).to_string(), "0/1");
+    assert_eq!(Fraction::from((6, 8)).to_string(), "3/4");
+    assert_eq!(Fraction::from((6, -8)).to_string(), "-3/4");
+    assert_eq!(Fraction::from((-6, -8)), Fraction::from((3, 4)));
+    let f: Fraction = (10, -4).into();
+    assert_eq!(f.to_string(), "-5/2");
+}
+
+#[test]
+fn test_fraction_equality() {
+    use std::collections::HashSet;
+
+    assert_eq!(Fraction::new(2, 4), Fraction::new(1, 2));
+    assert_ne!(Fraction::new(1, 2), Fraction::new(-1, 2));
+    let set: HashSet<Fraction> = [Fraction::new(1, 2), Fraction::new(2, 4), Fraction::new(-3, -6)].iter().copied().collect();
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn test_fraction_ordering() {
+    let mut fractions = [Fraction::new(2, 3), Fraction::new(1, 2), Fraction::new(-1, 3), Fraction::new(3, 4), Fraction::new(0, 1)];
+    fractions.sort();
+    let sorted: Vec<String> = fractions.iter().map(|f| f.to_string()).collect();
+    assert_eq!(sorted, ["-1/3", "0/1", "1/2", "2/3", "3/4"]);
+    assert!(Fraction::new(i64::MAX, 1) > Fraction::new(i64::MAX - 1, 1));
+    assert!(Fraction::new(1, i64::MAX) < Fraction::new(1, i64::MAX - 1));
+}
+
+#[test]
+fn test_continued_fraction() {
+    assert_eq!(continued_fraction(45, 16), [2, 1, 4, 3]);
+    assert_eq!(continued_fraction(3, 8), [0, 2, 1, 2]);
+    assert_eq!(continued_fraction(7, 1), [7]);
+    assert_eq!(from_continued_fraction(&[2, 1, 4, 3]), Fraction::new(45, 16));
+    assert_eq!(from_continued_fraction(&[0]), Fraction::new(0, 1));
+}
+
+// Going there and back should always land on the fraction we started from, in lowest terms.
+#[test]
+fn test_continued_fraction_round_trip() {
+    for num in 0..100 {
+        for den in 1..100 {
+            assert_eq!(from_continued_fraction(&continued_fraction(num, den)), Fraction::new(num as i64, den as i64));
+        }
+    }
+    // Neighbouring Fibonacci numbers give the longest continued fractions for their size, all 1s.
+    assert_eq!(from_continued_fraction(&continued_fraction(4660046610375530309, 7540113804746346429)).to_string(), "4660046610375530309/7540113804746346429");
+}
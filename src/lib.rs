@@ -0,0 +1,1030 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Modules let us split the library across files. mod parse; pulls in src/parse.rs, and pub use re-exports its public items so callers can write hello::parse_u64_token.
+mod fraction;
+mod parallel;
+mod parse;
+mod primes;
+pub use fraction::{continued_fraction, from_continued_fraction, Fraction};
+pub use parallel::{gcd_all_parallel, GcdCalculator, DEFAULT_PARALLEL_THRESHOLD};
+pub use parse::{format_u64_radix, parse_u64_token, parse_u64_token_radix, parse_u64_token_strict, strip_grouping, ParseTokenError};
+pub use primes::{common_divisors, divisors, is_probable_prime, legendre, mod_exp, nth_prime, prime_factors, FactorCache};
+
+// A Simple Function (part 1)
+
+// The arrow (token) precedes the return type. Our function returns a u64 value.
+// #[inline] lets the compiler copy the body of gcd into callers in other crates (like main.rs, or a bench), where a small hot function otherwise stays a call.
+#[inline]
+pub fn gcd(mut n: u64, mut m: u64) -> u64 {
+    // assert! is a marco that verifies that neither argument is zero. The ! character marks this as a macro invocation, not a function call. assert! checks that its argument is true, and if it is not, terminates the program (called a panic).
+    assert!(n != 0 && m != 0);
+    // The loop changes n and m, so keep the inputs for the check at the end.
+    let (a, b) = (n, m);
+    while m != 0 {
+        if m < n {
+            // std::mem::swap exchanges the values behind two mutable references (&mut). It does the same as going through a temporary: let t = m; m = n; n = t;
+            std::mem::swap(&mut n, &mut m);
+        }
+        m %= n;
+    }
+    // debug_assert! is assert! for debug builds only: cargo build and cargo test check it, but cargo build --release leaves it out, so it costs nothing in production.
+    // Whatever else goes wrong, a gcd has to divide both of the numbers it came from.
+    debug_assert!(a % n == 0 && b % n == 0, "gcd({}, {}) gave {}, which doesn't divide both", a, b, n);
+    // Rust has a return statement, but we don't need one here. If a function body ends with an expression that is NOT followed by a semicolon, that's the function's return value. In fact, any block surrounded by curly braces can function as an expression. Ex:
+    // {
+    //     println!("evaluating cos x");
+    //     x.cos()
+    // }
+    // The above is an expression that prints a message then yields x.cos() as it's value.
+    // It's typical in Rust to use this form to establish the function's value when control "falls off the end" of the function, and use return statements only for explicit early returns from the midst of a function.
+    n
+}
+
+// The same loop as gcd, but giving up with None after max_iters trips around it. The loop always finishes for correct code, in fewer than 100 iterations for any u64 inputs, so hitting the limit means something is broken.
+// This is a guard for contexts like FFI or embedded use where a runaway loop is worse than an error.
+pub fn gcd_bounded(mut n: u64, mut m: u64, max_iters: u32) -> Option<u64> {
+    assert!(n != 0 && m != 0);
+    let mut iters = 0;
+    while m != 0 {
+        if iters == max_iters {
+            return None;
+        }
+        iters += 1;
+        if m < n {
+            std::mem::swap(&mut n, &mut m);
+        }
+        m %= n;
+    }
+    Some(n)
+}
+
+// The same loop as gcd again, this time also returning how many times it went around. That count is what determines how long gcd takes.
+pub fn gcd_counting(mut n: u64, mut m: u64) -> (u64, u32) {
+    assert!(n != 0 && m != 0);
+    let mut steps = 0;
+    while m != 0 {
+        steps += 1;
+        if m < n {
+            std::mem::swap(&mut n, &mut m);
+        }
+        m %= n;
+    }
+    (n, steps)
+}
+
+// The same algorithm written recursively: the gcd of n and m is the gcd of m and n % m, until m reaches 0 and n is the answer.
+// Like gcd it refuses zero inputs, so the assert sits out here and the recursion itself, which does pass 0 down on its last step, lives in the inner function.
+// Rust doesn't promise to turn the self-call into a loop the way some languages do, but each call removes a Fibonacci-sized chunk, so it's never more than about 93 deep for u64s.
+// In practice the optimizer does make it a loop: benches/gcd.rs has it no slower than gcd (221 against 319 ns on random u64s, 262 against 328 for the Fibonacci worst case).
+pub fn gcd_recursive(n: u64, m: u64) -> u64 {
+    assert!(n != 0 && m != 0);
+    // A fn can be declared inside another. It's only visible in here, and it can't see the outer function's variables.
+    fn euclid(n: u64, m: u64) -> u64 {
+        if m == 0 {
+            n
+        } else {
+            euclid(m, n % m)
+        }
+    }
+    euclid(n, m)
+}
+
+// Folds op across a slice from the left: reduce_with(&[a, b, c], op) is op(op(a, b), c). An empty slice has nothing to start from, so that's None.
+// F is whatever function or closure the caller passes in, so the same fold serves gcd, lcm, max, or anything else that combines two u64s into one.
+pub fn reduce_with<F: Fn(u64, u64) -> u64>(values: &[u64], op: F) -> Option<u64> {
+    // split_first gives back the first element and the rest, or None when the slice is empty; ? passes that None straight back.
+    let (first, rest) = values.split_first()?;
+    Some(rest.iter().fold(*first, |acc, m| op(acc, *m)))
+}
+
+// The same for an op that can fail, like checked_lcm: the fold stops at the first None, and that's the answer.
+pub fn try_reduce_with<F: Fn(u64, u64) -> Option<u64>>(values: &[u64], op: F) -> Option<u64> {
+    let (first, rest) = values.split_first()?;
+    rest.iter().try_fold(*first, |acc, m| op(acc, *m))
+}
+
+// The gcd of a whole slice, folding gcd across it from the left. An empty slice has no gcd, so that's None.
+pub fn gcd_all(values: &[u64]) -> Option<u64> {
+    reduce_with(values, gcd)
+}
+
+// The same for anything that can be iterated over, like numbers being parsed lazily out of a huge file.
+// Once the running gcd reaches 1 it can never change again, so the loop stops there and the rest of the iterator is never pulled from.
+// A fold would always run to the end, and even a for loop asks for the next value before its body can check d, so the check comes first here.
+pub fn gcd_iter<I: IntoIterator<Item = u64>>(values: I) -> Option<u64> {
+    let mut values = values.into_iter();
+    let mut d = values.next()?;
+    while d != 1 {
+        match values.next() {
+            Some(m) => d = gcd(d, m),
+            None => break,
+        }
+    }
+    Some(d)
+}
+
+// The same again for an iterator of references, which is what iterating over &numbers gives, so callers can pass &numbers without writing a * anywhere.
+// copied turns each &u64 into the u64 it points at, and 'a is there because the references have to stay valid while the iterator is being read.
+pub fn gcd_refs<'a, I: IntoIterator<Item = &'a u64>>(values: I) -> Option<u64> {
+    gcd_iter(values.into_iter().copied())
+}
+
+// The step of the grid the values sit on: sorted, [22, 10, 16] is 10 16 22, the gaps between neighbors are 6 and 6, and their gcd is 6, so every value is 10 plus a multiple of 6.
+// A repeated value leaves a gap of 0, which gcd can't take, but gcd(d, 0) would be d anyway, so those gaps are skipped. If every value is the same there are no gaps left and the step is 0.
+// Fewer than two values have no gaps at all, so that's None.
+pub fn gcd_of_differences(values: &[u64]) -> Option<u64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    // windows(2) gives each neighboring pair as a two-element slice: [10, 16], then [16, 22].
+    let differences: Vec<u64> = sorted.windows(2).map(|pair| pair[1] - pair[0]).filter(|d| *d != 0).collect();
+    Some(gcd_all(&differences).unwrap_or(0))
+}
+
+// How the gcd of one group of numbers compares with another's: [12, 18] against [8, 12] is Greater, since 6 > 4.
+// None when either group is empty and so has no gcd. Ordering is the type sort and cmp use, so this slots straight into sort_by to rank groups by their gcd.
+pub fn compare_gcds(a: &[u64], b: &[u64]) -> Option<Ordering> {
+    Some(gcd_all(a)?.cmp(&gcd_all(b)?))
+}
+
+// The gcd of two numbers given as raw big-endian bytes (most significant byte first), as they arrive from a network protocol: [0x00, 0x2a] is 42.
+// Each slice can be at most 8 bytes, the size of a u64; anything longer panics, the same as gcd does for a zero, which an empty or all-zero slice would be.
+pub fn gcd_bytes(a: &[u8], b: &[u8]) -> u64 {
+    gcd(u64_from_be_slice(a), u64_from_be_slice(b))
+}
+
+// Right-aligns the bytes in an 8-byte buffer so a short slice gets leading zero bytes, then lets u64::from_be_bytes do the decoding.
+fn u64_from_be_slice(bytes: &[u8]) -> u64 {
+    assert!(bytes.len() <= 8, "a u64 is at most 8 bytes, got {}", bytes.len());
+    let mut buffer = [0u8; 8];
+    buffer[8 - bytes.len()..].copy_from_slice(bytes);
+    u64::from_be_bytes(buffer)
+}
+
+// True when no two of the values share a factor. That's stricter than gcd_all being 1: [6, 10, 15] has gcd 1, but every pair in it shares a factor.
+// Rather than try all n^2 pairs, each value is checked against the product of the ones before it, which shares a factor with it exactly when one of them does.
+// The product can outgrow a u64, so it's kept as a list of blocks that each fit, and a value is checked against every block.
+// A 0 counts as sharing a factor with everything but 1, since gcd(0, n) is n.
+pub fn pairwise_coprime(values: &[u64]) -> bool {
+    let mut blocks = vec![1u64];
+    for &v in values {
+        if blocks.iter().any(|&block| gcd_or_zero(block, v) != 1) {
+            return false;
+        }
+        let last = blocks.len() - 1;
+        match blocks[last].checked_mul(v) {
+            Some(product) => blocks[last] = product,
+            None => blocks.push(v),
+        }
+    }
+    true
+}
+
+// gcd, extended to zero with gcd(0, n) = n.
+fn gcd_or_zero(a: u64, b: u64) -> u64 {
+    if a == 0 || b == 0 {
+        a | b
+    } else {
+        gcd(a, b)
+    }
+}
+
+// Returns (g, a / g, b / g) where g = gcd(a, b): gcd_with_cofactors(12, 18) is (6, 2, 3). It saves callers both divisions and the chance of dividing by zero.
+// Unlike gcd this takes zeros, using gcd(n, 0) = n: (12, 0) gives (12, 1, 0). When both are zero g is 0 too, and there's nothing sensible to divide, so that's (0, 0, 0).
+pub fn gcd_with_cofactors(a: u64, b: u64) -> (u64, u64, u64) {
+    let g = gcd_or_zero(a, b);
+    if g == 0 {
+        return (0, 0, 0);
+    }
+    (g, a / g, b / g)
+}
+
+// How many bits smaller a and b get from dividing out their gcd, counting each number's bits as 64 minus its leading zeros: for 3 * 2^40 and 5 * 2^40 that's 80, two lots of 40.
+// Zeros go through gcd_with_cofactors, so (a, 0) reduces to (1, 0), saving all but one of a's bits, and (0, 0) saves nothing.
+pub fn reduction_bits(a: u64, b: u64) -> u32 {
+    let bits = |n: u64| 64 - n.leading_zeros();
+    let (_, a_reduced, b_reduced) = gcd_with_cofactors(a, b);
+    (bits(a) + bits(b)) - (bits(a_reduced) + bits(b_reduced))
+}
+
+// The content of a polynomial, given as its coefficients: the gcd of their sizes, ignoring signs. 6x^2 - 9x + 12 is [6, -9, 12], with content 3.
+// A zero coefficient is just a missing term and doesn't change the answer. With no nonzero coefficients at all the content is 0, the zero polynomial's.
+pub fn content(coeffs: &[i64]) -> u64 {
+    let magnitudes: Vec<u64> = coeffs.iter().map(|c| c.unsigned_abs()).filter(|c| *c != 0).collect();
+    gcd_all(&magnitudes).unwrap_or(0)
+}
+
+// The gcd of signed 128-bit numbers, ignoring their signs: [-12, 18] gives 6. None for an empty slice.
+// The sizes are taken with unsigned_abs, straight into a u128. i128::MIN.abs() would overflow, since 2^127 is one more than the largest i128, but as a u128 it fits.
+// Zeros are allowed, with gcd(0, n) = n, so a slice of nothing but zeros has gcd 0.
+pub fn gcd_i128(values: &[i128]) -> Option<u128> {
+    let (first, rest) = values.split_first()?;
+    Some(rest.iter().fold(first.unsigned_abs(), |d, v| gcd_u128(d, v.unsigned_abs())))
+}
+
+// The Euclidean loop again, for u128s, written the way that lets zeros through: gcd(n, 0) stops straight away with n.
+fn gcd_u128(mut n: u128, mut m: u128) -> u128 {
+    while m != 0 {
+        let r = n % m;
+        n = m;
+        m = r;
+    }
+    n
+}
+
+// The gcd of fixed-point decimals, each given as (mantissa, scale) for mantissa / 10^scale: (15, 1) is 1.5 and (125, 2) is 1.25.
+// Mantissas at different scales can't be compared directly, so each is first brought up to the largest scale by appending zeros (1.5 becomes 150 hundredths),
+// and the answer is at that scale too: for 1.5 and 1.25 that's (25, 2), 0.25. Zeros are skipped the same way content skips them, and with nothing else left the gcd is 0.
+// A mantissa that no longer fits in a u64 once scaled up panics.
+pub fn gcd_scaled(values: &[(u64, u32)]) -> (u64, u32) {
+    let scale = values.iter().map(|(_, scale)| *scale).max().unwrap_or(0);
+    let mantissas: Vec<u64> = values
+        .iter()
+        .filter(|(mantissa, _)| *mantissa != 0)
+        .map(|(mantissa, from)| {
+            10u64
+                .checked_pow(scale - from)
+                .and_then(|factor| mantissa.checked_mul(factor))
+                .expect("mantissa is too large for a u64 at the common scale")
+        })
+        .collect();
+    (gcd_all(&mantissas).unwrap_or(0), scale)
+}
+
+// Divide and Conquer
+
+// The shape of a divide-and-conquer gcd: each Node holds the gcd of everything below it, and the Leaves are the input numbers.
+// An enum variant can't hold another copy of its own enum directly, since that would have no fixed size, so children go in a Box on the heap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GcdTree {
+    Leaf(u64),
+    Node { gcd: u64, left: Box<GcdTree>, right: Box<GcdTree> },
+}
+
+impl GcdTree {
+    pub fn gcd(&self) -> u64 {
+        match self {
+            GcdTree::Leaf(value) => *value,
+            GcdTree::Node { gcd, .. } => *gcd,
+        }
+    }
+}
+
+// Splits the values in half, builds a tree for each half, and joins the two with a node holding the gcd of both. None for an empty slice.
+// Same answer as gcd_all, but pairing things up this way keeps the tree only log2(n) levels deep.
+pub fn gcd_tree(values: &[u64]) -> Option<GcdTree> {
+    match values {
+        [] => None,
+        [value] => Some(GcdTree::Leaf(*value)),
+        _ => {
+            let (left, right) = values.split_at(values.len() / 2);
+            let left = gcd_tree(left)?;
+            let right = gcd_tree(right)?;
+            let g = gcd(left.gcd(), right.gcd());
+            Some(GcdTree::Node { gcd: g, left: Box::new(left), right: Box::new(right) })
+        }
+    }
+}
+
+// The gcd of three numbers, for call sites that would otherwise have to build a slice. Like gcd, none of them may be zero.
+pub fn gcd3(a: u64, b: u64, c: u64) -> u64 {
+    gcd(gcd(a, b), c)
+}
+
+// Any unsigned integer that converts to a u64 without losing anything. u64::from already does this for each of them, so every impl just calls it;
+// the trait gives gcd_any a single bound to ask for, instead of separate generic code for each width.
+pub trait IntoU64 {
+    fn into_u64(self) -> u64;
+}
+
+impl IntoU64 for u8 {
+    fn into_u64(self) -> u64 {
+        u64::from(self)
+    }
+}
+
+impl IntoU64 for u16 {
+    fn into_u64(self) -> u64 {
+        u64::from(self)
+    }
+}
+
+impl IntoU64 for u32 {
+    fn into_u64(self) -> u64 {
+        u64::from(self)
+    }
+}
+
+impl IntoU64 for u64 {
+    fn into_u64(self) -> u64 {
+        self
+    }
+}
+
+// gcd for two numbers of whatever unsigned widths the caller has, so gcd_any(42u32, 56u16) works without any as casts. A and B are picked separately, so they needn't match.
+pub fn gcd_any<A: IntoU64, B: IntoU64>(a: A, b: B) -> u64 {
+    gcd(a.into_u64(), b.into_u64())
+}
+
+// Caching Results
+
+// Remembers the gcd of every pair it's asked about, for workloads where the same pairs come up again and again.
+// gcd(a, b) and gcd(b, a) are the same, so pairs are stored smallest first and both orders share one entry.
+#[derive(Debug, Default)]
+pub struct GcdCache {
+    map: HashMap<(u64, u64), u64>,
+}
+
+impl GcdCache {
+    pub fn new() -> GcdCache {
+        GcdCache::default()
+    }
+
+    // The entry API looks the key up once: or_insert_with only runs gcd when the pair isn't there yet.
+    pub fn gcd(&mut self, a: u64, b: u64) -> u64 {
+        let key = (a.min(b), a.max(b));
+        *self.map.entry(key).or_insert_with(|| gcd(key.0, key.1))
+    }
+}
+
+// Tracing the Euclidean Algorithm
+
+// One division step: a = quotient * b + remainder. The next step divides b by the remainder, and the last step is the one with a remainder of 0.
+// #[derive] asks the compiler to write these trait implementations for us: Debug for printing with {:?}, and the comparisons so tests can assert_eq! on steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcdStep {
+    pub a: u64,
+    pub b: u64,
+    pub quotient: u64,
+    pub remainder: u64,
+}
+
+// Runs the same algorithm as gcd but records each division along the way. The larger number is divided first, so the first step of gcd_steps(18, 48) is 48 = 2*18 + 12.
+// The gcd is the b of the last step.
+pub fn gcd_steps(n: u64, m: u64) -> Vec<GcdStep> {
+    assert!(n != 0 && m != 0);
+    let (mut a, mut b) = if n >= m { (n, m) } else { (m, n) };
+    let mut steps = Vec::new();
+    while b != 0 {
+        let step = GcdStep { a, b, quotient: a / b, remainder: a % b };
+        steps.push(step);
+        a = b;
+        b = step.remainder;
+    }
+    steps
+}
+
+// Binary GCD (Stein's algorithm)
+
+// Gets the same answer as gcd using only shifts and subtraction, no division. Division is one of the slower things a CPU does, so this can win on large inputs.
+// It leans on three facts: gcd(2a, 2b) = 2*gcd(a, b), gcd(2a, b) = gcd(a, b) when b is odd, and gcd(a, b) = gcd(a, b - a).
+pub fn binary_gcd(mut n: u64, mut m: u64) -> u64 {
+    assert!(n != 0 && m != 0);
+    // trailing_zeros counts how many times 2 divides a number. The twos common to both come back at the end as a shift.
+    let shift = (n | m).trailing_zeros();
+    n >>= n.trailing_zeros();
+    loop {
+        m >>= m.trailing_zeros();
+        // Both are odd now; keep n as the smaller so the subtraction can't go below zero.
+        if n > m {
+            std::mem::swap(&mut n, &mut m);
+        }
+        m -= n;
+        if m == 0 {
+            return n << shift;
+        }
+    }
+}
+
+// The Euclidean gcd with one trick borrowed from binary_gcd: the power of two the inputs share is shifted off first and put back at the end,
+// so the division loop works on smaller numbers. That helps most with inputs that are both divisible by a high power of two. gcd itself stays as the plain version.
+pub fn gcd_fast(n: u64, m: u64) -> u64 {
+    assert!(n != 0 && m != 0);
+    let shift = (n | m).trailing_zeros();
+    gcd(n >> shift, m >> shift) << shift
+}
+
+// Choosing an Algorithm
+
+// The two ways this library has of computing a gcd, for callers that want to pick one at run time, like the CLI's --algorithm flag.
+// Algorithm::default() is the one to use when the caller hasn't picked, which is binary whatever the input; #[default] marks it for #[derive(Default)].
+// It takes no size because size makes no difference: in benches/gcd.rs binary is the faster of the two per call at every size, 7 ns against 9 for small numbers, 140 against 320
+// for random u64s and 48 against 337 for the Fibonacci worst case, and since the cost per call doesn't depend on how many numbers there are, there's no batch size where Euclid catches up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    Euclid,
+    #[default]
+    Binary,
+}
+
+impl Algorithm {
+    pub fn gcd(self, n: u64, m: u64) -> u64 {
+        match self {
+            Algorithm::Euclid => gcd(n, m),
+            Algorithm::Binary => binary_gcd(n, m),
+        }
+    }
+}
+
+// What "stein".parse::<Algorithm>() gives back, or any other name it doesn't know. It keeps the name so the message can show it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAlgorithmError {
+    name: String,
+}
+
+impl fmt::Display for ParseAlgorithmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown gcd algorithm {:?}, expected euclid or binary", self.name)
+    }
+}
+
+impl std::error::Error for ParseAlgorithmError {}
+
+// Implementing FromStr is what lets str's parse method produce an Algorithm. Names are matched ignoring case, so "Binary" and "BINARY" work too.
+impl FromStr for Algorithm {
+    type Err = ParseAlgorithmError;
+
+    fn from_str(name: &str) -> Result<Algorithm, ParseAlgorithmError> {
+        match name.to_ascii_lowercase().as_str() {
+            "euclid" => Ok(Algorithm::Euclid),
+            "binary" => Ok(Algorithm::Binary),
+            _ => Err(ParseAlgorithmError { name: name.to_string() }),
+        }
+    }
+}
+
+// The Extended Euclidean Algorithm
+
+// As well as the gcd g of a and b, this finds whole numbers x and y with a*x + b*y == g (Bezout's identity). It returns (g, x, y).
+// One of x or y is usually negative, so they're i128: a u64 times a coefficient can't overflow 128 bits, since |x| is never more than b.
+pub fn extended_gcd(a: u64, b: u64) -> (u64, i128, i128) {
+    // Each pair holds the previous and current value of the remainder and both coefficients. Tuples let us update a pair in one assignment.
+    let (mut old_r, mut r) = (a as i128, b as i128);
+    let (mut old_x, mut x) = (1i128, 0i128);
+    let (mut old_y, mut y) = (0i128, 1i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    (old_r as u64, old_x, old_y)
+}
+
+// Least Common Multiples
+
+// The lcm is the smallest number both n and m divide into. Dividing by the gcd before multiplying keeps the intermediate value as small as possible, but the result itself can still be too big for a u64, so there are three flavors depending on what the caller wants when that happens:
+// checked_lcm returns None on overflow, saturating_lcm clamps to u64::MAX, and plain lcm wraps around (silently giving a wrong answer), which is only right when the caller already knows the result fits.
+// Like gcd, all three require both numbers to be nonzero.
+pub fn checked_lcm(n: u64, m: u64) -> Option<u64> {
+    (n / gcd(n, m)).checked_mul(m)
+}
+
+pub fn saturating_lcm(n: u64, m: u64) -> u64 {
+    (n / gcd(n, m)).saturating_mul(m)
+}
+
+pub fn lcm(n: u64, m: u64) -> u64 {
+    (n / gcd(n, m)).wrapping_mul(m)
+}
+
+// Checks gcd(a, b) * lcm(a, b) == a * b, which should always be true. When a * b is too big for a u64 the check can't be done honestly, so that's None rather than a misleading answer.
+pub fn gcd_lcm_identity_holds(a: u64, b: u64) -> Option<bool> {
+    let product = a.checked_mul(b)?;
+    let lhs = gcd(a, b).checked_mul(checked_lcm(a, b)?)?;
+    Some(lhs == product)
+}
+
+// The lcm of a whole slice, folding checked_lcm across it. None when the slice is empty or the lcm gets too big for a u64 along the way. Like checked_lcm, none of the values may be zero.
+// A plain reduce_with(values, lcm) would wrap around on overflow without a word, so this uses the fallible version.
+pub fn lcm_all(values: &[u64]) -> Option<u64> {
+    try_reduce_with(values, checked_lcm)
+}
+
+// The same, but with the running lcm kept in a u128, which has room for 64 more bits before it overflows. The inputs are still u64s.
+// The gcd step needs no u128 arithmetic: gcd(l, m) is gcd(m, l % m), and l % m is less than m, so it fits in a u64.
+// Like lcm_all, none of the values may be zero. Asserting that up front gives a clear panic rather than one from dividing by zero part way through.
+pub fn lcm_all_u128(values: &[u64]) -> Option<u128> {
+    assert!(!values.contains(&0), "lcm_all_u128 requires nonzero values");
+    let (first, rest) = values.split_first()?;
+    rest.iter().try_fold(*first as u128, |l, m| {
+        let g = gcd_or_zero(*m, (l % *m as u128) as u64);
+        (l / g as u128).checked_mul(*m as u128)
+    })
+}
+
+// The lcm of the values if it's at most bound, for questions like "when do all these schedules next line up, within the next bound ticks?". None when it's bigger, or there are no values.
+// The running lcm never gets smaller as values are added, so the fold gives up as soon as it passes bound instead of working out an lcm that's already too big, and an lcm too big for a u64 is certainly past bound too.
+pub fn lcm_all_under(values: &[u64], bound: u64) -> Option<u64> {
+    let (first, rest) = values.split_first()?;
+    let check = |l: u64| if l <= bound { Some(l) } else { None };
+    rest.iter().try_fold(check(*first)?, |l, m| check(checked_lcm(l, *m)?))
+}
+
+// Reducing Fractions
+
+// Divides num and den by their gcd, giving the fraction num/den in lowest terms: reduce_fraction(6, 8) is (3, 4).
+// Zero needs special care because gcd refuses it. 0/den is zero however it's written, so it always comes back as (0, 1).
+// A zero denominator isn't a fraction at all; rather than guess, those inputs come back unchanged for the caller to deal with.
+pub fn reduce_fraction(num: u64, den: u64) -> (u64, u64) {
+    if den == 0 {
+        return (num, den);
+    }
+    if num == 0 {
+        return (0, 1);
+    }
+    let g = gcd(num, den);
+    (num / g, den / g)
+}
+
+// Time Periods
+
+// The gcd of some periods, the longest tick that every one of them is a whole number of: 12s and 18s give 6s. None for an empty slice.
+// A Duration is whole seconds plus nanoseconds, so each one is turned into a total count of nanoseconds and the gcd is taken over those.
+// That's exact down to the nanosecond, the finest a Duration can go: 1.5s and 1s give 0.5s. The total can be more than a u64 holds, so it's a u128.
+// A zero period is a multiple of every tick, so it doesn't change the answer, and if every period is zero the answer is zero too.
+pub fn gcd_durations(durations: &[Duration]) -> Option<Duration> {
+    if durations.is_empty() {
+        return None;
+    }
+    // gcd_u128 lets zeros through, and starting from 0 works because gcd(0, m) is m.
+    let d = durations.iter().fold(0, |d, duration| gcd_u128(d, duration.as_nanos()));
+    // d is no bigger than the longest period, so splitting it back up always fits in a Duration.
+    Some(Duration::new((d / 1_000_000_000) as u64, (d % 1_000_000_000) as u32))
+}
+
+// Generating Test Data
+
+// count pseudo-random numbers that depend only on seed, for benchmarking without an input file. The same seed always gives the same numbers.
+// This is the SplitMix64 generator: add a fixed odd constant, then scramble the bits. It's not for anything secret, but it's fast and its output is well spread.
+// gcd refuses 0, so the (astronomically unlikely) 0 is bumped up to 1.
+pub fn seeded_numbers(count: usize, seed: u64) -> Vec<u64> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            (z ^ (z >> 31)).max(1)
+        })
+        .collect()
+}
+
+// Writing and Running Unit Tests (part 2)
+
+// The below definition mark test_gcd as a test function, to be skipped in normal compilations, but included and called automatically if we run our program with cargo test.
+// #[test] is called an attribute. Attributes are an open-ended system for marking functions and other declarations with extra info. They're used to control compiler warnings and code style checks, include code conditionally, tell Rust how to interact with code written in other languages, etc.
+#[test]
+// Defining a function which calls gcd and checks that it returns correct values.
+fn test_gcd() {
+    assert_eq!(gcd(14, 15), 1);
+
+    assert_eq!(gcd(2*3*5*11*17, 3*7*11*13*19), 3*11);
+}
+
+
+#[test]
+fn test_gcd_argument_order() {
+    for n in 1..100 {
+        for m in 1..100 {
+            assert_eq!(gcd(n, m), gcd(m, n));
+        }
+    }
+    assert_eq!(gcd(u64::MAX, 5), 5);
+}
+
+// The largest values a u64 can hold. The loop only ever uses % and swaps, and a remainder is always smaller than what it divides, so nothing can overflow.
+// Tests are debug builds, where arithmetic overflow panics rather than wrapping, so these passing is the proof.
+#[test]
+fn test_gcd_max_values() {
+    assert_eq!(gcd(u64::MAX, 1), 1);
+    assert_eq!(gcd(1, u64::MAX), 1);
+    assert_eq!(gcd(u64::MAX, u64::MAX), u64::MAX);
+    // Neighbouring numbers never share a factor.
+    assert_eq!(gcd(u64::MAX, u64::MAX - 1), 1);
+    assert_eq!(gcd(u64::MAX - 1, u64::MAX), 1);
+    // u64::MAX is 3 * 5 * 17 * 257 * 641 * 65537 * 6700417, and u64::MAX - 1 is 2 * (2^63 - 1).
+    assert_eq!(gcd(u64::MAX, 3 * 65537 * 1024), 3 * 65537);
+    assert_eq!(gcd(u64::MAX - 1, 1 << 63), 2);
+}
+
+#[test]
+fn test_binary_gcd() {
+    assert_eq!(binary_gcd(14, 15), 1);
+    assert_eq!(binary_gcd(2*3*5*11*17, 3*7*11*13*19), 3*11);
+    for n in 1..200 {
+        for m in 1..200 {
+            assert_eq!(binary_gcd(n, m), gcd(n, m));
+        }
+    }
+}
+
+#[test]
+fn test_gcd_bounded() {
+    assert_eq!(gcd_bounded(2*3*5*11*17, 3*7*11*13*19, 100), Some(3*11));
+    // The largest Fibonacci pair that fits in a u64 is the slowest input there is, and it still fits comfortably under 100.
+    assert_eq!(gcd_bounded(7540113804746346429, 12200160415121876738, 100), Some(1));
+    assert_eq!(gcd_bounded(48, 18, 2), None);
+}
+
+// Neighbouring Fibonacci numbers are the worst case for the Euclidean algorithm (Lame's theorem): each step only knocks them back to the previous pair.
+// So gcd(fib(k), fib(k + 1)) is always 1 and takes exactly k - 1 steps. If gcd ever took more, it would no longer be logarithmic in the size of its inputs.
+#[test]
+fn test_fibonacci_worst_case() {
+    // fib[k] is the kth Fibonacci number; fib(93) is the largest that fits in a u64.
+    let mut fib = vec![0u64, 1];
+    for k in 2..=93 {
+        fib.push(fib[k - 1] + fib[k - 2]);
+    }
+    for k in 2..=92 {
+        assert_eq!(gcd_counting(fib[k], fib[k + 1]), (1, k as u32 - 1));
+    }
+}
+
+// gcd_recursive is written so differently from gcd that agreeing on every pair is a good sign both are right, including pairs where the first is smaller and the Fibonacci worst case.
+#[test]
+fn test_gcd_recursive() {
+    assert_eq!(gcd_recursive(48, 18), 6);
+    assert_eq!(gcd_recursive(18, 48), 6);
+    assert_eq!(gcd_recursive(7540113804746346429, 4660046610375530309), 1);
+    for n in 1..200 {
+        for m in 1..200 {
+            assert_eq!(gcd_recursive(n, m), gcd(n, m));
+        }
+    }
+    for n in seeded_numbers(100, 7) {
+        assert_eq!(gcd_recursive(n, u64::MAX), gcd(n, u64::MAX));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_gcd_recursive_zero() {
+    gcd_recursive(0, 5);
+}
+
+// Tests build with debug assertions on, so this runs gcd's own check that its answer divides both inputs, over a spread of sizes up to u64::MAX.
+#[test]
+fn test_gcd_debug_assertions() {
+    for n in seeded_numbers(200, 1) {
+        for m in [1, 2, 3, 1 << 32, u64::MAX, n / 3 + 1, n ^ 0xffff] {
+            let d = gcd(n, m);
+            assert_eq!((n % d, m % d), (0, 0));
+        }
+    }
+}
+
+// Checks gcd against the most obvious possible definition, for every pair of numbers from 1 to 255: count down from the smaller one until something divides both.
+// That's 65,025 pairs, few enough to try them all, so for inputs this size gcd is shown right rather than just spot-checked.
+#[test]
+fn test_gcd_all_u8_pairs() {
+    for a in 1..=255u64 {
+        for b in 1..=255u64 {
+            let reference = (1..=a.min(b)).rev().find(|d| a.is_multiple_of(*d) && b.is_multiple_of(*d)).unwrap();
+            assert_eq!(gcd(a, b), reference, "gcd({}, {})", a, b);
+        }
+    }
+}
+
+// Not a benchmark (that's benches/gcd.rs), just a tripwire: the slowest u64 input should take well under a microsecond, so if
+// a thousand runs of it average anything near a millisecond, gcd has become dramatically slower. The bound is loose enough that a slow or busy CI machine won't trip it.
+// black_box keeps the compiler from working the answer out ahead of time and skipping the loop.
+#[test]
+fn test_gcd_worst_case_time() {
+    use std::hint::black_box;
+    use std::time::{Duration, Instant};
+
+    let start = Instant::now();
+    for _ in 0..1000 {
+        assert_eq!(gcd(black_box(7540113804746346429), black_box(12200160415121876738)), 1);
+    }
+    let average = start.elapsed() / 1000;
+    assert!(average < Duration::from_millis(1), "gcd took {:?} per call on the worst case", average);
+}
+
+#[test]
+fn test_gcd_all() {
+    assert_eq!(gcd_all(&[]), None);
+    assert_eq!(gcd_all(&[42]), Some(42));
+    assert_eq!(gcd_all(&[42, 56, 98]), Some(14));
+}
+
+#[test]
+fn test_reduce_with() {
+    assert_eq!(reduce_with(&[], gcd), None);
+    assert_eq!(reduce_with(&[42, 56, 98], gcd), Some(14));
+    assert_eq!(reduce_with(&[4, 6, 10], lcm), Some(60));
+    assert_eq!(reduce_with(&[3, 9, 4], |a, b| a.max(b)), Some(9));
+    assert_eq!(try_reduce_with(&[4, 6, 10], checked_lcm), Some(60));
+    assert_eq!(try_reduce_with(&[u64::MAX, 2, 3], checked_lcm), None);
+    assert_eq!(try_reduce_with(&[], checked_lcm), None);
+}
+
+#[test]
+fn test_gcd_refs() {
+    let numbers = vec![42, 56, 98];
+    assert_eq!(gcd_refs(&numbers), Some(14));
+    assert_eq!(gcd_refs(&numbers[1..]), Some(14));
+    assert_eq!(gcd_refs(numbers.iter().filter(|n| **n != 98)), Some(14));
+    assert_eq!(gcd_refs(&Vec::new()), None);
+}
+
+#[test]
+fn test_gcd_of_differences() {
+    assert_eq!(gcd_of_differences(&[10, 16, 22]), Some(6));
+    assert_eq!(gcd_of_differences(&[22, 10, 16]), Some(6));
+    assert_eq!(gcd_of_differences(&[3, 11, 7, 7]), Some(4));
+    assert_eq!(gcd_of_differences(&[5, 5]), Some(0));
+    assert_eq!(gcd_of_differences(&[5]), None);
+    assert_eq!(gcd_of_differences(&[]), None);
+}
+
+#[test]
+fn test_compare_gcds() {
+    assert_eq!(compare_gcds(&[12, 18], &[8, 12]), Some(Ordering::Greater));
+    assert_eq!(compare_gcds(&[8, 12], &[12, 18]), Some(Ordering::Less));
+    assert_eq!(compare_gcds(&[6], &[12, 18]), Some(Ordering::Equal));
+    assert_eq!(compare_gcds(&[], &[12, 18]), None);
+    assert_eq!(compare_gcds(&[12, 18], &[]), None);
+}
+
+#[test]
+fn test_gcd_iter() {
+    assert_eq!(gcd_iter(Vec::new()), None);
+    assert_eq!(gcd_iter(vec![42, 56, 98]), Some(14));
+    assert_eq!(gcd_iter((1..=10).map(|n| n * 6)), Some(6));
+
+    // 4 and 9 are coprime, so nothing after them should ever be looked at.
+    let values = [4, 9, 0, 0].iter().enumerate().map(|(i, n)| {
+        assert!(i < 2, "gcd_iter read past the point where the gcd reached 1");
+        *n
+    });
+    assert_eq!(gcd_iter(values), Some(1));
+}
+
+#[test]
+fn test_gcd_bytes() {
+    assert_eq!(gcd_bytes(&[0x00, 0x2a], &[0x38]), 14);
+    assert_eq!(gcd_bytes(&[0x01, 0x00], &[0x00, 0x00, 0x00, 0xc0]), 64);
+    assert_eq!(gcd_bytes(&[0xff; 8], &[0xff; 8]), u64::MAX);
+}
+
+#[test]
+#[should_panic(expected = "a u64 is at most 8 bytes")]
+fn test_gcd_bytes_too_long() {
+    gcd_bytes(&[1; 9], &[1]);
+}
+
+#[test]
+fn test_pairwise_coprime() {
+    assert!(pairwise_coprime(&[3, 4, 5]));
+    assert!(!pairwise_coprime(&[6, 10, 15]));
+    assert_eq!(gcd_all(&[6, 10, 15]), Some(1));
+    assert!(pairwise_coprime(&[]));
+    assert!(pairwise_coprime(&[1, 0, 1]));
+    assert!(!pairwise_coprime(&[0, 2]));
+    // Big enough to overflow a single running product, with the shared factor 3 at the very end.
+    let big = [4294967311, 4294967291, 3 * 1000003, 7, 3];
+    assert!(pairwise_coprime(&big[..4]));
+    assert!(!pairwise_coprime(&big));
+}
+
+#[test]
+fn test_gcd_with_cofactors() {
+    assert_eq!(gcd_with_cofactors(12, 18), (6, 2, 3));
+    assert_eq!(gcd_with_cofactors(7, 13), (1, 7, 13));
+    assert_eq!(gcd_with_cofactors(12, 0), (12, 1, 0));
+    assert_eq!(gcd_with_cofactors(0, 0), (0, 0, 0));
+}
+
+#[test]
+fn test_content() {
+    assert_eq!(content(&[6, -9, 12]), 3);
+    assert_eq!(content(&[-4, 0, 10]), 2);
+    assert_eq!(content(&[i64::MIN, i64::MIN]), 1 << 63);
+    assert_eq!(content(&[0, 0]), 0);
+    assert_eq!(content(&[]), 0);
+}
+
+#[test]
+fn test_gcd_i128() {
+    assert_eq!(gcd_i128(&[]), None);
+    assert_eq!(gcd_i128(&[-12, 18]), Some(6));
+    assert_eq!(gcd_i128(&[-7]), Some(7));
+    assert_eq!(gcd_i128(&[0, -5, 0]), Some(5));
+    assert_eq!(gcd_i128(&[0, 0]), Some(0));
+    // i128::MIN is -2^127, whose size only fits in a u128.
+    assert_eq!(gcd_i128(&[i128::MIN]), Some(1 << 127));
+    assert_eq!(gcd_i128(&[i128::MIN, i128::MIN]), Some(1 << 127));
+    assert_eq!(gcd_i128(&[i128::MIN, 3 << 100]), Some(1 << 100));
+    assert_eq!(gcd_i128(&[i128::MIN, i128::MAX]), Some(1));
+}
+
+#[test]
+fn test_gcd_scaled() {
+    assert_eq!(gcd_scaled(&[(15, 1), (125, 2)]), (25, 2));
+    assert_eq!(gcd_scaled(&[(250, 2), (75, 2)]), (25, 2));
+    assert_eq!(gcd_scaled(&[(3, 0), (15, 1)]), (15, 1));
+    assert_eq!(gcd_scaled(&[(0, 3), (4, 1)]), (400, 3));
+    assert_eq!(gcd_scaled(&[]), (0, 0));
+}
+
+#[test]
+#[should_panic(expected = "mantissa is too large")]
+fn test_gcd_scaled_overflow() {
+    gcd_scaled(&[(u64::MAX, 0), (1, 1)]);
+}
+
+#[test]
+fn test_reduction_bits() {
+    assert_eq!(reduction_bits(3 << 40, 5 << 40), 80);
+    assert_eq!(reduction_bits(7, 13), 0);
+    assert_eq!(reduction_bits(12, 18), 5);
+    assert_eq!(reduction_bits(12, 0), 3);
+    assert_eq!(reduction_bits(0, 0), 0);
+}
+
+#[test]
+fn test_gcd_tree() {
+    assert_eq!(gcd_tree(&[]), None);
+    assert_eq!(gcd_tree(&[5]), Some(GcdTree::Leaf(5)));
+    let tree = gcd_tree(&[12, 18, 8]).unwrap();
+    assert_eq!(
+        tree,
+        GcdTree::Node {
+            gcd: 2,
+            left: Box::new(GcdTree::Leaf(12)),
+            right: Box::new(GcdTree::Node {
+                gcd: 2,
+                left: Box::new(GcdTree::Leaf(18)),
+                right: Box::new(GcdTree::Leaf(8)),
+            }),
+        }
+    );
+}
+
+#[test]
+fn test_gcd3() {
+    assert_eq!(gcd3(24, 36, 48), 12);
+    assert_eq!(gcd3(6, 10, 15), 1);
+}
+
+#[test]
+fn test_gcd_any() {
+    assert_eq!(gcd_any(42u32, 56u16), 14);
+    assert_eq!(gcd_any(255u8, 85u8), 85);
+    assert_eq!(gcd_any(u64::MAX, 5u8), 5);
+    assert_eq!(gcd_any(65535u16, 4294967295u32), 65535);
+}
+
+#[test]
+fn test_gcd_cache() {
+    let mut cache = GcdCache::new();
+    assert_eq!(cache.gcd(84, 36), 12);
+    assert_eq!(cache.map.len(), 1);
+
+    // Planting a wrong answer shows the next lookup really comes from the cache, and that (36, 84) finds the same entry as (84, 36).
+    cache.map.insert((36, 84), 999);
+    assert_eq!(cache.gcd(84, 36), 999);
+    assert_eq!(cache.gcd(36, 84), 999);
+    assert_eq!(cache.map.len(), 1);
+
+    assert_eq!(cache.gcd(14, 15), 1);
+    assert_eq!(cache.map.len(), 2);
+}
+
+#[test]
+fn test_gcd_steps() {
+    assert_eq!(
+        gcd_steps(18, 48),
+        vec![
+            GcdStep { a: 48, b: 18, quotient: 2, remainder: 12 },
+            GcdStep { a: 18, b: 12, quotient: 1, remainder: 6 },
+            GcdStep { a: 12, b: 6, quotient: 2, remainder: 0 },
+        ]
+    );
+}
+
+#[test]
+fn test_gcd_fast() {
+    for n in 1..300 {
+        for m in 1..300 {
+            assert_eq!(gcd_fast(n, m), gcd(n, m));
+        }
+    }
+    assert_eq!(gcd_fast(1 << 63, 1 << 40), 1 << 40);
+    assert_eq!(gcd_fast(3 << 50, 9 << 52), 3 << 50);
+    assert_eq!(gcd_fast(u64::MAX, u64::MAX - 1), 1);
+}
+
+#[test]
+fn test_extended_gcd() {
+    assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+    assert_eq!(extended_gcd(14, 15), (1, -1, 1));
+}
+
+#[test]
+fn test_lcm() {
+    assert_eq!(lcm(4, 6), 12);
+    assert_eq!(checked_lcm(4, 6), Some(12));
+    assert_eq!(saturating_lcm(4, 6), 12);
+
+    // These two are coprime, so their lcm is their product, which is far past u64::MAX.
+    let (n, m) = (u64::MAX, u64::MAX - 1);
+    assert_eq!(checked_lcm(n, m), None);
+    assert_eq!(saturating_lcm(n, m), u64::MAX);
+    assert_eq!(lcm(n, m), n.wrapping_mul(m));
+}
+
+#[test]
+fn test_lcm_all() {
+    assert_eq!(lcm_all(&[]), None);
+    assert_eq!(lcm_all(&[4, 6, 10]), Some(60));
+    assert_eq!(lcm_all_u128(&[]), None);
+    assert_eq!(lcm_all_u128(&[4, 6, 10]), Some(60));
+
+    // Each of these is coprime to the others, so the lcm is their product, about 2^96: too big for a u64, but fine in a u128.
+    let values = [u32::MAX as u64, u32::MAX as u64 - 1, u32::MAX as u64 - 2];
+    assert_eq!(lcm_all(&values), None);
+    assert_eq!(lcm_all_u128(&values), Some(values.iter().map(|v| *v as u128).product()));
+    assert_eq!(lcm_all_u128(&[u64::MAX, u64::MAX - 1, u64::MAX - 2]), None);
+}
+
+#[test]
+#[should_panic(expected = "lcm_all_u128 requires nonzero values")]
+fn test_lcm_all_u128_zero() {
+    lcm_all_u128(&[4, 0, 6]);
+}
+
+#[test]
+fn test_lcm_all_under() {
+    assert_eq!(lcm_all_under(&[4, 6, 10], 100), Some(60));
+    assert_eq!(lcm_all_under(&[4, 6, 10], 60), Some(60));
+    assert_eq!(lcm_all_under(&[4, 6, 10], 59), None);
+    assert_eq!(lcm_all_under(&[200], 100), None);
+    assert_eq!(lcm_all_under(&[], 100), None);
+    // The lcm overflows a u64 here, which is over any bound.
+    assert_eq!(lcm_all_under(&[u32::MAX as u64, u32::MAX as u64 - 1, u32::MAX as u64 - 2], u64::MAX), None);
+}
+
+#[test]
+fn test_gcd_lcm_identity() {
+    assert_eq!(gcd_lcm_identity_holds(12, 18), Some(true));
+    assert_eq!(gcd_lcm_identity_holds(1 << 32, 1 << 31), Some(true));
+    // The lcm of these fits in a u64 (it's 2^40), but their product doesn't.
+    assert_eq!(gcd_lcm_identity_holds(1 << 40, 1 << 39), None);
+}
+
+#[test]
+fn test_reduce_fraction() {
+    assert_eq!(reduce_fraction(6, 8), (3, 4));
+    assert_eq!(reduce_fraction(3, 4), (3, 4));
+    assert_eq!(reduce_fraction(0, 8), (0, 1));
+    assert_eq!(reduce_fraction(6, 0), (6, 0));
+    assert_eq!(reduce_fraction(0, 0), (0, 0));
+}
+
+#[test]
+fn test_gcd_durations() {
+    assert_eq!(gcd_durations(&[]), None);
+    assert_eq!(gcd_durations(&[Duration::from_secs(12), Duration::from_secs(18)]), Some(Duration::from_secs(6)));
+    assert_eq!(gcd_durations(&[Duration::from_millis(1500), Duration::from_secs(1)]), Some(Duration::from_millis(500)));
+    assert_eq!(gcd_durations(&[Duration::ZERO, Duration::from_secs(4)]), Some(Duration::from_secs(4)));
+    assert_eq!(gcd_durations(&[Duration::ZERO]), Some(Duration::ZERO));
+    assert_eq!(gcd_durations(&[Duration::MAX]), Some(Duration::MAX));
+}
+
+#[test]
+fn test_seeded_numbers() {
+    let numbers = seeded_numbers(100, 42);
+    assert_eq!(numbers.len(), 100);
+    assert_eq!(numbers, seeded_numbers(100, 42));
+    assert_ne!(numbers, seeded_numbers(100, 43));
+    // A shorter run is the start of a longer one.
+    assert_eq!(seeded_numbers(10, 42), numbers[..10]);
+}
+
+#[test]
+fn test_algorithm_default() {
+    assert_eq!(Algorithm::default(), Algorithm::Binary);
+}
+
+#[test]
+fn test_parse_algorithm() {
+    for name in &["euclid", "Euclid", "EUCLID"] {
+        assert_eq!(name.parse(), Ok(Algorithm::Euclid));
+    }
+    for name in &["binary", "Binary", "bInArY"] {
+        assert_eq!(name.parse(), Ok(Algorithm::Binary));
+    }
+    let error = "stein".parse::<Algorithm>().unwrap_err();
+    assert_eq!(error.to_string(), "unknown gcd algorithm \"stein\", expected euclid or binary");
+    assert_eq!(Algorithm::Binary.gcd(48, 18), 6);
+}
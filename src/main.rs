@@ -1,88 +1,2924 @@
 // Handling Command-Line Arguments (Part 3)
 
-// The 'use' declaration brings the two traits Write and FromStr into scope. A trait is a collection of methods that types can implement. Although we never use Write of FromStr in the program, a trait must be in scope in order to use its methods.
+// The 'use' declaration brings the traits BufRead and Write into scope. A trait is a collection of methods that types can implement. Although we never name BufRead or Write directly in the program, a trait must be in scope in order to use its methods.
+// BufRead is implemented by buffered readers like a locked stdin. We use it (and the Read methods it builds on, like read_to_string) to pull numbers in from stdin.
 // Any type that implements the Write trait has a write_fmt method that writes formatted text to a stream. the std::io::strderr type implements Write, and we'll use the writeIn! macro to print error msgs; that macro expands to code that uses the write_fmt method.
-use std::io::Write;
-// Any type that implements the FromStr trait has a from_str method that tries to parse a value of that type from a string. The u64 type implements FromStr, and we'll call u64::from_str to parse our command-line arguments.
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+// TryFrom is for conversions that can fail, like a big i128 into an i64. Edition 2018 doesn't have it in scope by default.
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+// Any type that implements the FromStr trait has a from_str method that tries to parse a value of that type from a string. It's what str's parse method calls, and whole_number below works for any type that implements it.
 use std::str::FromStr;
 
+// The output module holds the formatters that turn a finished calculation into the line we print.
+mod output;
+use output::{explain_json, json_string, list_text, trace_json, GcdOutcome, Json, Ndjson, NumberOnly, OutputFormatter};
+#[cfg(not(feature = "minimal"))]
+use output::Prose;
+// The CSV reader is only compiled in with the csv feature, the same as --glob.
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "regex")]
+mod regex;
+#[cfg(feature = "regex")]
+use regex::Pattern;
+
+// Without the regex feature there are no patterns to be had. An enum with no variants can never have a value, so Option<Pattern> is always None
+// and the code handling a pattern still compiles, without anything being able to reach it.
+#[cfg(not(feature = "regex"))]
+#[derive(Debug)]
+enum Pattern {}
+
+#[cfg(not(feature = "regex"))]
+impl Pattern {
+    fn split<'t>(&self, _text: &'t str) -> Vec<&'t str> {
+        match *self {}
+    }
+}
+
+// The number theory itself lives in the library half of this package (src/lib.rs), which cargo names after the package. main.rs only deals with the command line.
+use hello::{
+    binary_gcd, checked_lcm, compare_gcds, content, divisors, extended_gcd, format_u64_radix, gcd, gcd_all, gcd_bounded, gcd_steps, gcd_tree, nth_prime, pairwise_coprime,
+    parse_u64_token_radix, parse_u64_token_strict, prime_factors, reduce_fraction, seeded_numbers, Algorithm, FactorCache, GcdCalculator, GcdStep, GcdTree,
+    strip_grouping, ParseAlgorithmError, ParseTokenError, DEFAULT_PARALLEL_THRESHOLD,
+};
+
 // Main function doesn't return a value so we can omit the ->
 fn main() {
+    // std::env::args returns an iterator, a value that produces each argument on demand, and indicates when we're done. Iterators are things that'd we normally want to loop over and Rust's standard library contains many types.
+    // The first value produced by std::... is the name of the program being run, so we skip() over it. collect() gathers the rest into a Vec<String> so run can look at them as a slice.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // All of the real work happens in run, which reads and writes whatever streams it is handed. Passing it the real stdin, stdout and stderr here, and in-memory buffers from the tests, lets the tests check exactly what the program would print.
+    let result = run(&args, &mut std::io::stdin().lock(), &mut PipeStdout(std::io::stdout()), &mut PipeStderr(std::io::stderr()));
+    let code = report(result, &mut PipeStderr(std::io::stderr()));
+
+    // Rust assumes that if main returns at all, the program finished successfully. Only by explicitly calling functions like expect or std::process::exit can we cause the program to terminate with an error status code.
+    if code != 0 {
+        std::process::exit(code);
+    }
+
+    // We can run the program from cmd line cargo run 42 56 or 42 56 80, or 42, or none at all.
+}
+
+// stdout, except that the reader going away ends the program quietly. When output is piped into something like head, which stops reading once it has enough,
+// the next write fails with a broken pipe error, and the unwraps after every writeln! would turn that into a panic. Leaving with status 0 is what
+// Unix tools do here: there's nobody left to tell. To see it, run yes 12 | head -100000 | gcd --lines --ndjson | head -1.
+struct PipeStdout(std::io::Stdout);
+
+impl Write for PipeStdout {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        exit_on_broken_pipe(self.0.write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        exit_on_broken_pipe(self.0.flush())
+    }
+}
+
+// Every other result, error or not, is handed back as it was.
+fn exit_on_broken_pipe<T>(result: std::io::Result<T>) -> std::io::Result<T> {
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => std::process::exit(0),
+        other => other,
+    }
+}
+
+// stderr can be a pipe too, as in gcd --max-value 0 1 2 2>&1 | head -0. There the program should still finish and exit with its real status,
+// so a write to a closed stderr is dropped and reported as done, rather than ending everything early the way a closed stdout does.
+struct PipeStderr(std::io::Stderr);
+
+impl Write for PipeStderr {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        ignore_broken_pipe(self.0.write(buf), buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        ignore_broken_pipe(self.0.flush(), ())
+    }
+}
+
+// A broken pipe counts as a write that went through, giving back value as its result. Any other result is handed back as it was.
+fn ignore_broken_pipe<T>(result: std::io::Result<T>, value: T) -> std::io::Result<T> {
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(value),
+        other => other,
+    }
+}
+
+// Printed when there's nothing to compute with. A minimal build keeps only the first line's worth, since every string here ends up in the binary.
+#[cfg(feature = "minimal")]
+const USAGE: &str = "Usage: gcd [OPTION]... NUMBER ...";
+#[cfg(not(feature = "minimal"))]
+const USAGE: &str = "Usage: gcd [--file PATH]... [--glob PATTERN]... [--csv PATH [--column N | NAME] [--header]]... [--exec COMMAND]... [--input-base N] [--stdin | - | --merge-stdin] [--stdin-radix N] [--seed-numbers COUNT [--seed SEED]] [--explain | --verbose | --trace-json | --explain-json] [--assert-coprime] [--fail-on-zero] [--min-gcd N] [--max-value N] [--require-count N] [--limit N] [--pairwise-coprime] [--coprime-count] [--from-ranges] [--max-iterations N] [--no-input-error] [--histogram] [--factor-common] [--progress] [--tree-output] [--parallel-threshold N [--dedup]] [--bare-list | --no-echo | --json | --ndjson | --exit-code] [--tee PATH] [--algorithm euclid|binary] [--verify] [--dry-run] [--strict] [--grouping comma|space|none] [--null-separated | -0] [--input-sep PATTERN] NUMBER ...\n       gcd --lines [--newline | --space | --ndjson] [--keep-going] < LISTS\n       gcd --batch-file PATH [--output PATH] [--ndjson] [--keep-going]\n       gcd NUMBER ... -- NUMBER ... [-- NUMBER ...]...\n       gcd --as-fraction DECIMAL ...\n       gcd reduce [--validate-fraction] NUM/DEN ...\n       gcd compare NUMBER ... -- NUMBER ...\n\nExit status: 0 success, 1 usage error, 2 parse error, 3 I/O error, 4 overflow, 5 check failed, or the gcd itself with --exit-code";
+
+// Exit Codes
+
+// Each kind of failure has its own exit status, so a script can tell a typo in its arguments from a missing file:
+// 1 usage error (bad flags or arguments), 2 parse error (input that isn't a number), 3 I/O error (a file or stdin couldn't be read),
+// 4 overflow (a number or result too big for a u64), and 5 when a check like --assert-coprime fails.
+// Giving enum variants explicit values means `code as i32` turns one straight into the number passed to std::process::exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitCode {
+    Usage = 1,
+    Parse = 2,
+    Io = 3,
+    Overflow = 4,
+    CheckFailed = 5,
+}
+
+// An error message along with which kind of failure it is.
+#[derive(Debug)]
+struct CliError {
+    code: ExitCode,
+    message: String,
+}
+
+// One constructor per kind keeps the call sites short: CliError::usage("...").
+// impl Into<String> accepts both a &str and a String, so a plain message and a format! one both work.
+impl CliError {
+    fn usage(message: impl Into<String>) -> CliError {
+        CliError { code: ExitCode::Usage, message: message.into() }
+    }
+
+    fn parse(message: impl Into<String>) -> CliError {
+        CliError { code: ExitCode::Parse, message: message.into() }
+    }
+
+    fn io(message: impl Into<String>) -> CliError {
+        CliError { code: ExitCode::Io, message: message.into() }
+    }
+
+    fn overflow(message: impl Into<String>) -> CliError {
+        CliError { code: ExitCode::Overflow, message: message.into() }
+    }
+
+    fn check_failed(message: impl Into<String>) -> CliError {
+        CliError { code: ExitCode::CheckFailed, message: message.into() }
+    }
+}
+
+// Turns the result of run into the program's exit status, writing any error to stderr on the way.
+// Success is usually 0, but --exit-code makes it the gcd.
+fn report(result: Result<i32, CliError>, stderr: &mut dyn Write) -> i32 {
+    match result {
+        Ok(status) => status,
+        Err(error) => {
+            // writeLn! macro allows us to write our error message to the stderr stream we were given.
+            // The .unwrap() call is a terse way to check that the attempt to print the error msg did not itself fail. If stderr has been closed there's nobody to tell,
+            // but the exit status can still say what went wrong, so a broken pipe is let go rather than turned into a panic.
+            ignore_broken_pipe(writeln!(stderr, "{}", error.message), ()).unwrap();
+            error.code as i32
+        }
+    }
+}
+
+// Where a group of numbers comes from. Keeping them in a Vec in the order given means the numbers end up in the same order the user listed them.
+enum Source {
+    Arg(String),
+    File(String),
+    Glob(String),
+    Csv(String),
+    Exec(String),
+    Stdin,
+    Seeded { count: usize, seed: u64 },
+}
+
+// Which CSV column to read, by position counting from 0 or by its name in the header row.
+// Only the CSV reader looks inside, so without the csv feature the compiler would warn that the values are never read.
+#[cfg_attr(not(feature = "csv"), allow(dead_code))]
+enum Column {
+    Index(usize),
+    Name(String),
+}
+
+// The subcommands the program understands. Plain gcd is what runs when no subcommand word is given.
+enum Command {
+    Gcd,
+    Bezout,
+    NthPrime,
+    Divisors,
+    Convert,
+    Repl,
+    Lcm,
+    Content,
+    Reduce,
+    Compare,
+}
+
+// Everything the command line asked for. Flags can appear anywhere, so we read them all first and only then start parsing numbers, which lets --input-base apply to numbers that came before it.
+struct Options {
+    command: Command,
+    sources: Vec<Source>,
+    input_base: u32,
+    stdin_radix: Option<u32>,
+    explain: bool,
+    assert_coprime: bool,
+    from_ranges: bool,
+    max_iterations: Option<u32>,
+    lines: bool,
+    separator: &'static str,
+    no_input_error: bool,
+    histogram: bool,
+    parallel_threshold: usize,
+    bare_list: bool,
+    pairwise_coprime: bool,
+    dedup: bool,
+    tree_output: bool,
+    as_fraction: bool,
+    tee: Option<String>,
+    algorithm: Option<Algorithm>,
+    output_base: u32,
+    verify: bool,
+    verbose: bool,
+    strict: bool,
+    formatter: Box<dyn OutputFormatter>,
+    grouping: Option<char>,
+    explain_overflow: bool,
+    min_gcd: Option<u64>,
+    csv_column: Column,
+    csv_header: bool,
+    factor_common: bool,
+    progress: bool,
+    null_separated: bool,
+    batch_file: Option<String>,
+    output: Option<String>,
+    max_value: Option<u64>,
+    ndjson: bool,
+    input_sep: Option<Pattern>,
+    fail_on_zero: bool,
+    require_count: Option<usize>,
+    group_breaks: Vec<usize>,
+    trace_json: bool,
+    explain_json: bool,
+    keep_going: bool,
+    exit_code: bool,
+    coprime_count: bool,
+    dry_run: bool,
+    validate_fraction: bool,
+}
+
+impl Options {
+    // How to read number tokens that are in base radix. That's input_base for everything except stdin, which can have its own --stdin-radix.
+    fn number_format(&self, radix: u32) -> NumberFormat<'_> {
+        NumberFormat {
+            radix,
+            strict: self.strict,
+            grouping: self.grouping,
+            null_separated: self.null_separated,
+            input_sep: self.input_sep.as_ref(),
+        }
+    }
+}
+
+// How numbers are written in the input: everything parse_tokens and parse_number need to know besides the text itself.
+// input_sep borrows the pattern from Options; the 'a says a NumberFormat can't outlive the Options it came from. A shared reference is Copy, so NumberFormat still is.
+#[derive(Debug, Clone, Copy)]
+struct NumberFormat<'a> {
+    radix: u32,
+    strict: bool,
+    grouping: Option<char>,
+    null_separated: bool,
+    input_sep: Option<&'a Pattern>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, CliError> {
+    let mut options = Options {
+        command: Command::Gcd,
+        sources: Vec::new(),
+        input_base: 10,
+        stdin_radix: None,
+        explain: false,
+        assert_coprime: false,
+        from_ranges: false,
+        max_iterations: None,
+        lines: false,
+        separator: "\n",
+        no_input_error: false,
+        histogram: false,
+        parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+        bare_list: false,
+        pairwise_coprime: false,
+        dedup: false,
+        tree_output: false,
+        as_fraction: false,
+        tee: None,
+        algorithm: None,
+        output_base: 10,
+        verify: false,
+        verbose: false,
+        strict: false,
+        formatter: default_formatter(false),
+        grouping: None,
+        explain_overflow: false,
+        min_gcd: None,
+        csv_column: Column::Index(0),
+        csv_header: false,
+        factor_common: false,
+        progress: false,
+        null_separated: false,
+        batch_file: None,
+        output: None,
+        max_value: None,
+        ndjson: false,
+        input_sep: None,
+        fail_on_zero: false,
+        require_count: None,
+        group_breaks: Vec::new(),
+        trace_json: false,
+        explain_json: false,
+        keep_going: false,
+        exit_code: false,
+        coprime_count: false,
+        dry_run: false,
+        validate_fraction: false,
+    };
+
+    // A subcommand, if any, has to be the very first argument.
+    let command = match args.first().map(|arg| arg.as_str()) {
+        Some("bezout") => Some(Command::Bezout),
+        Some("nthprime") => Some(Command::NthPrime),
+        Some("divisors") => Some(Command::Divisors),
+        Some("convert") => Some(Command::Convert),
+        Some("repl") => Some(Command::Repl),
+        Some("lcm") => Some(Command::Lcm),
+        Some("content") => Some(Command::Content),
+        Some("reduce") => Some(Command::Reduce),
+        Some("compare") => Some(Command::Compare),
+        _ => None,
+    };
+    let args = match command {
+        Some(command) => {
+            options.command = command;
+            &args[1..]
+        }
+        None => args,
+    };
+
+    // Calling next() ourselves, instead of using a plain for loop, lets a flag like --file take the following argument as its value.
+    let mut args = args.iter();
+    let mut stdin_at_end = false;
+    let mut merge_stdin = false;
+    let mut seed_count = None;
+    let mut seed = 0;
+    let mut no_echo = false;
+    let mut json = false;
+    let mut limit = None;
+    while let Some(arg) = args.next() {
+        if arg == "--file" {
+            // --file may be repeated; each file's numbers are appended in the order the files were given.
+            let path = args.next().ok_or_else(|| CliError::usage("--file requires a PATH argument"))?;
+            options.sources.push(Source::File(path.clone()));
+        } else if arg == "--glob" {
+            let pattern = args.next().ok_or_else(|| CliError::usage("--glob requires a PATTERN argument"))?;
+            options.sources.push(Source::Glob(pattern.clone()));
+        } else if arg == "--exec" {
+            let command = args.next().ok_or_else(|| CliError::usage("--exec requires a COMMAND argument"))?;
+            options.sources.push(Source::Exec(command.clone()));
+        } else if arg == "--csv" {
+            let path = args.next().ok_or_else(|| CliError::usage("--csv requires a PATH argument"))?;
+            options.sources.push(Source::Csv(path.clone()));
+        } else if arg == "--column" {
+            // A number picks a column by position; anything else is a name to look up in the header row.
+            let value = args.next().ok_or_else(|| CliError::usage("--column requires a column number or name"))?;
+            options.csv_column = match value.parse() {
+                Ok(index) => Column::Index(index),
+                Err(_) => Column::Name(value.clone()),
+            };
+        } else if arg == "--header" {
+            options.csv_header = true;
+        } else if arg == "--seed-numbers" {
+            let value = args.next().ok_or_else(|| CliError::usage("--seed-numbers requires a COUNT"))?;
+            seed_count = Some(whole_number(value, "--seed-numbers")?);
+        } else if arg == "--seed" {
+            let value = args.next().ok_or_else(|| CliError::usage("--seed requires a SEED"))?;
+            seed = whole_number(value, "--seed")?;
+        } else if arg == "--input-base" {
+            options.input_base = parse_radix(args.next(), "--input-base")?;
+        } else if arg == "--from" {
+            // For convert, --from is just another name for --input-base, so VALUE goes through the same parser as every other number.
+            options.input_base = parse_radix(args.next(), "--from")?;
+        } else if arg == "--to" {
+            options.output_base = parse_radix(args.next(), "--to")?;
+        } else if arg == "--assert-coprime" {
+            options.assert_coprime = true;
+        } else if arg == "--coprime-count" {
+            options.coprime_count = true;
+        } else if arg == "--pairwise-coprime" {
+            options.pairwise_coprime = true;
+        } else if arg == "--from-ranges" {
+            options.from_ranges = true;
+        } else if arg == "--max-iterations" {
+            let value = args.next().ok_or_else(|| CliError::usage("--max-iterations requires a count"))?;
+            options.max_iterations = Some(whole_number(value, "--max-iterations")?);
+        } else if arg == "--no-input-error" {
+            options.no_input_error = true;
+        } else if arg == "--input-sep" {
+            let pattern = args.next().ok_or_else(|| CliError::usage("--input-sep requires a PATTERN argument"))?;
+            options.input_sep = Some(input_sep(pattern)?);
+        } else if arg == "--null-separated" || arg == "-0" {
+            options.null_separated = true;
+        } else if arg == "--progress" {
+            if !cfg!(feature = "progress") {
+                return Err(CliError::usage("--progress requires building with `--features progress`"));
+            }
+            options.progress = true;
+        } else if arg == "--factor-common" {
+            options.factor_common = true;
+        } else if arg == "--histogram" {
+            options.histogram = true;
+        } else if arg == "--parallel-threshold" {
+            let value = args.next().ok_or_else(|| CliError::usage("--parallel-threshold requires a count"))?;
+            options.parallel_threshold = whole_number(value, "--parallel-threshold")?;
+        } else if arg == "--json" {
+            json = true;
+        } else if arg == "--ndjson" {
+            options.ndjson = true;
+        } else if arg == "--no-echo" {
+            no_echo = true;
+        } else if arg == "--grouping" {
+            let value = args.next().ok_or_else(|| CliError::usage("--grouping requires comma, space or none"))?;
+            options.grouping = match value.as_str() {
+                "comma" => Some(','),
+                "space" => Some(' '),
+                "none" => None,
+                _ => return Err(CliError::usage(format!("--grouping must be comma, space or none, got {:?}", value))),
+            };
+        } else if arg == "--min-gcd" {
+            let value = args.next().ok_or_else(|| CliError::usage("--min-gcd requires a number"))?;
+            options.min_gcd = Some(whole_number(value, "--min-gcd")?);
+        } else if arg == "--max-value" {
+            let value = args.next().ok_or_else(|| CliError::usage("--max-value requires a number"))?;
+            options.max_value = Some(whole_number(value, "--max-value")?);
+        } else if arg == "--explain-overflow" {
+            options.explain_overflow = true;
+        } else if arg == "--limit" {
+            let value = args.next().ok_or_else(|| CliError::usage("--limit requires a count"))?;
+            limit = Some(whole_number(value, "--limit")?);
+        } else if arg == "--require-count" {
+            let value = args.next().ok_or_else(|| CliError::usage("--require-count requires a count"))?;
+            options.require_count = Some(whole_number(value, "--require-count")?);
+        } else if arg == "--fail-on-zero" {
+            options.fail_on_zero = true;
+        } else if arg == "--strict" {
+            options.strict = true;
+        } else if arg == "--validate-fraction" {
+            options.validate_fraction = true;
+        } else if arg == "--dry-run" {
+            options.dry_run = true;
+        } else if arg == "--verify" {
+            options.verify = true;
+        } else if arg == "--dedup" {
+            options.dedup = true;
+        } else if arg == "--bare-list" {
+            options.bare_list = true;
+        } else if arg == "--lines" {
+            options.lines = true;
+        } else if arg == "--keep-going" {
+            options.keep_going = true;
+        } else if arg == "--exit-code" {
+            options.exit_code = true;
+        } else if arg == "--newline" {
+            options.separator = "\n";
+        } else if arg == "--space" {
+            options.separator = " ";
+        } else if arg == "--as-fraction" {
+            options.as_fraction = true;
+        } else if arg == "--algorithm" {
+            let name = args.next().ok_or_else(|| CliError::usage("--algorithm requires euclid or binary"))?;
+            options.algorithm = Some(name.parse().map_err(|e: ParseAlgorithmError| CliError::usage(e.to_string()))?);
+        } else if arg == "--batch-file" {
+            let path = args.next().ok_or_else(|| CliError::usage("--batch-file requires a PATH argument"))?;
+            options.batch_file = Some(path.clone());
+        } else if arg == "--output" {
+            let path = args.next().ok_or_else(|| CliError::usage("--output requires a PATH argument"))?;
+            options.output = Some(path.clone());
+        } else if arg == "--tee" {
+            let path = args.next().ok_or_else(|| CliError::usage("--tee requires a PATH argument"))?;
+            options.tee = Some(path.clone());
+        } else if arg == "--tree-output" {
+            options.tree_output = true;
+        } else if arg == "--trace-json" {
+            options.trace_json = true;
+        } else if arg == "--explain-json" {
+            options.explain_json = true;
+        } else if arg == "--explain" {
+            options.explain = true;
+        } else if arg == "--verbose" {
+            // --verbose is --explain with a closing summary line.
+            options.explain = true;
+            options.verbose = true;
+        } else if arg == "--stdin" {
+            stdin_at_end = true;
+        } else if arg == "--merge-stdin" {
+            merge_stdin = true;
+        } else if arg == "--stdin-radix" {
+            options.stdin_radix = Some(parse_radix(args.next(), "--stdin-radix")?);
+        } else if arg == "--" {
+            // Each -- ends one group of numbers and starts the next, so what's recorded is how many sources came before it.
+            options.group_breaks.push(options.sources.len());
+        } else if arg == "-" {
+            // Following the Unix convention, a lone - means "read stdin here", so its numbers land at this point in the list.
+            options.sources.push(Source::Stdin);
+        } else {
+            options.sources.push(Source::Arg(arg.clone()));
+        }
+    }
+
+    // The formatter for the final line is picked once here, so print_gcd never has to look at these flags.
+    options.formatter = if json {
+        Box::new(Json)
+    } else if options.ndjson {
+        Box::new(Ndjson)
+    } else if no_echo {
+        Box::new(NumberOnly)
+    } else {
+        default_formatter(options.bare_list)
+    };
+
+    // Generated numbers come after everything given explicitly. Without --seed the seed is 0.
+    if let Some(count) = seed_count {
+        options.sources.push(Source::Seeded { count, seed });
+    }
+    // --merge-stdin is --stdin for adding piped numbers to those in --file: every file is read first, in order, then stdin.
+    // Without a --file there's nothing to merge with, which most likely means the flag was meant for something else.
+    if merge_stdin {
+        if !options.sources.iter().any(|source| matches!(source, Source::File(_))) {
+            return Err(CliError::usage("--merge-stdin adds stdin to the numbers from --file, so it needs at least one --file"));
+        }
+        stdin_at_end = true;
+    }
+    // --stdin is the same as a - after everything else.
+    if stdin_at_end {
+        options.sources.push(Source::Stdin);
+    }
+    // stdin can only be read once, so asking for it twice is a mistake rather than something we can do.
+    let stdin_reads = options.sources.iter().filter(|source| matches!(source, Source::Stdin)).count();
+    if stdin_reads > 1 {
+        return Err(CliError::usage("stdin can only be read once: give at most one of - and --stdin"));
+    }
+    // --limit caps how many numbers can be typed out as arguments, guarding against a runaway shell glob or xargs. Files and stdin don't count towards it.
+    if let Some(limit) = limit {
+        let count = options.sources.iter().filter(|source| matches!(source, Source::Arg(_))).count();
+        if count > limit {
+            return Err(CliError::usage(format!(
+                "got {} numbers as arguments, but --limit allows at most {}; for more than that, put them in a file and pass --file PATH (or pipe them in with --stdin)",
+                count, limit
+            )));
+        }
+    }
+    if matches!(options.csv_column, Column::Name(_)) && !options.csv_header {
+        return Err(CliError::usage("--column can only be given a name along with --header"));
+    }
+    // The answer to --assert-coprime is the exit status and a sentence, which has no JSON form, so asking for JSON as well is a mistake rather than something to quietly ignore.
+    if options.assert_coprime && (json || options.ndjson) {
+        return Err(CliError::usage("--assert-coprime answers in words and with its exit status, so it can't be combined with --json or --ndjson"));
+    }
+    // The same goes for --pairwise-coprime.
+    if options.pairwise_coprime && (json || options.ndjson) {
+        return Err(CliError::usage("--pairwise-coprime answers in words and with its exit status, so it can't be combined with --json or --ndjson"));
+    }
+    if options.keep_going && !options.lines && options.batch_file.is_none() {
+        return Err(CliError::usage("--keep-going is for the line-by-line modes, --lines and --batch-file"));
+    }
+    // --dry-run promises nothing is computed, and these modes take their input their own way and work on it as they go, so they can't just stop short of computing.
+    let computes_as_it_reads = options.lines || options.batch_file.is_some() || options.as_fraction;
+    if options.dry_run && (computes_as_it_reads || matches!(options.command, Command::Repl | Command::Content | Command::Reduce)) {
+        return Err(CliError::usage("--dry-run can't be combined with --lines, --batch-file, --as-fraction, or the repl, content and reduce subcommands"));
+    }
+    let other_mode = options.lines || options.batch_file.is_some() || options.as_fraction || options.dry_run || !options.group_breaks.is_empty();
+    if options.exit_code && (other_mode || !matches!(options.command, Command::Gcd) || options.tee.is_some() || options.output.is_some()) {
+        return Err(CliError::usage("--exit-code answers with the gcd of one list of numbers, and prints nothing, so it can't be combined with other modes or output"));
+    }
+    if options.tee.is_some() && options.output.is_some() {
+        return Err(CliError::usage("--tee and --output can't be combined: --tee copies stdout to a file, --output sends it to one instead"));
+    }
+    // --strict numbers are canonical decimal, so there's no other base for them to be in.
+    if options.strict && (options.input_base != 10 || options.stdin_radix.is_some()) {
+        return Err(CliError::usage("--strict only accepts decimal numbers, so it can't be combined with --input-base or --stdin-radix"));
+    }
+    Ok(options)
+}
+
+#[cfg(feature = "regex")]
+fn input_sep(pattern: &str) -> Result<Pattern, CliError> {
+    Pattern::new(pattern).map_err(|e| CliError::usage(format!("--input-sep pattern {:?}: {}", pattern, e)))
+}
+
+#[cfg(not(feature = "regex"))]
+fn input_sep(_pattern: &str) -> Result<Pattern, CliError> {
+    Err(CliError::usage("--input-sep requires building with `--features regex`"))
+}
+
+// The sentence the result is normally printed in. A minimal build leaves Prose out altogether and prints just the number, the same as --no-echo.
+#[cfg(not(feature = "minimal"))]
+fn default_formatter(bare_list: bool) -> Box<dyn OutputFormatter> {
+    Box::new(Prose { bare_list })
+}
+
+#[cfg(feature = "minimal")]
+fn default_formatter(_bare_list: bool) -> Box<dyn OutputFormatter> {
+    Box::new(NumberOnly)
+}
+
+// Parses the value given to a flag like --seed as a whole number. The turbofish-free T is worked out from wherever the result is stored, so the same function reads a u32, u64 or usize.
+fn whole_number<T: FromStr>(value: &str, flag: &str) -> Result<T, CliError> {
+    value.parse().map_err(|_| CliError::usage(format!("{} must be a whole number, got {:?}", flag, value)))
+}
+
+// Digits run 0-9 then a-z, so bases go from 2 through 36 and anything else is refused up front.
+fn parse_radix(value: Option<&String>, flag: &str) -> Result<u32, CliError> {
+    let value = value.ok_or_else(|| CliError::usage(format!("{} requires a base argument", flag)))?;
+    match value.parse::<u32>() {
+        Ok(radix) if (2..=36).contains(&radix) => Ok(radix),
+        _ => Err(CliError::usage(format!("{} must be a base from 2 to 36, got {:?}", flag, value))),
+    }
+}
+
+// The ? operator used below returns early from run with the Err value whenever one of the calls fails, so each error only needs describing once, where it happens.
+// Results go to stdout. stderr is for remarks about the run, like how many numbers --max-value dropped, that a script reading stdout shouldn't have to filter out.
+fn run(args: &[String], stdin: &mut dyn BufRead, stdout: &mut dyn Write, stderr: &mut dyn Write) -> Result<i32, CliError> {
+    let options = parse_options(args)?;
+    if options.exit_code {
+        return gcd_status(&options, stdin, stderr);
+    }
+    write_output(&options, stdin, stdout, stderr)?;
+    Ok(0)
+}
+
+// Runs the chosen mode and sends what it prints to stdout, or to the files --output and --tee name.
+fn write_output(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write, stderr: &mut dyn Write) -> Result<(), CliError> {
+    // With --output nothing goes to stdout; the file gets it all. It's written even when dispatch fails part way, so a batch with a bad line still leaves its results behind.
+    if let Some(path) = &options.output {
+        let mut output = Vec::new();
+        let result = dispatch(options, stdin, &mut output, stderr);
+        let written = std::fs::write(path, &output);
+        result?;
+        return written.map_err(|e| CliError::io(format!("error writing {}: {}", path, e)));
+    }
+    let path = match &options.tee {
+        Some(path) => path,
+        None => return dispatch(options, stdin, stdout, stderr),
+    };
+
+    // With --tee the output is collected in memory first. A Vec<u8> implements Write, so dispatch can't tell it apart from stdout.
+    // Whatever was produced goes to stdout before the file is written, so a bad PATH never hides the result from the screen.
+    let mut output = Vec::new();
+    let result = dispatch(options, stdin, &mut output, stderr);
+    stdout.write_all(&output).unwrap();
+    let written = std::fs::write(path, &output);
+    result?;
+    written.map_err(|e| CliError::io(format!("error writing {}: {}", path, e)))
+}
+
+// Runs whichever mode the options asked for, writing its output to stdout.
+fn dispatch(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write, stderr: &mut dyn Write) -> Result<(), CliError> {
+    if options.lines {
+        return print_lines(options, stdin, stdout, stderr);
+    }
+    if let Some(path) = &options.batch_file {
+        return print_batch(path, options, stdout);
+    }
+    if options.as_fraction {
+        return print_fractions(options, stdout);
+    }
+    if matches!(options.command, Command::Repl) {
+        return run_repl(options, stdin, stdout);
+    }
+    if matches!(options.command, Command::Content) {
+        return print_content(options, stdout);
+    }
+    if matches!(options.command, Command::Reduce) {
+        return print_reduced(options, stdout);
+    }
+    if options.dry_run {
+        return dry_run(options, stdin, stdout);
+    }
+    if matches!(options.command, Command::Compare) {
+        return print_ordering(options, stdin, stdout);
+    }
+    if !options.group_breaks.is_empty() {
+        return compare_groups(options, stdin, stdout);
+    }
+    let numbers = input_numbers(options, stdin, stderr)?;
+
+    match options.command {
+        Command::Gcd => print_gcd(&numbers, options, stdout),
+        Command::Bezout => print_bezout(&numbers, stdout),
+        Command::NthPrime => print_nth_prime(&numbers, stdout),
+        Command::Divisors => print_divisors(&numbers, options, stdout),
+        Command::Convert => print_conversion(&numbers, options, stdout),
+        Command::Lcm => print_lcm(&numbers, options, stdout),
+        Command::Repl | Command::Content | Command::Reduce | Command::Compare => unreachable!(),
+    }
+}
+
+// Reads every number the sources give, then applies the checks and filters that come before any computing.
+fn input_numbers(options: &Options, stdin: &mut dyn BufRead, stderr: &mut dyn Write) -> Result<Vec<u64>, CliError> {
+    let mut numbers = collect_numbers(options, stdin)?;
+    // --require-count checks what was read, before --max-value drops anything, so it catches a stage upstream losing or doubling numbers.
+    match options.require_count {
+        Some(count) if numbers.len() != count => {
+            return Err(CliError::check_failed(format!("expected {} numbers but read {}", count, numbers.len())));
+        }
+        _ => {}
+    }
+    if let Some(max) = options.max_value {
+        drop_above(&mut numbers, max, stderr);
+    }
+    if options.fail_on_zero {
+        reject_zero(&numbers)?;
+    }
+    Ok(numbers)
+}
+
+// With --exit-code the gcd is the exit status instead of being printed, so a shell script can write gcd --exit-code 12 18; echo $?.
+// An exit status is a single byte, so a gcd over 255 is an overflow error. The statuses 1 to 5 are also the error codes, and the only way to tell
+// a gcd of 2 from a parse error is that an error writes its message to stderr. --no-input-error with nothing to read gives 0, the gcd it would have printed.
+fn gcd_status(options: &Options, stdin: &mut dyn BufRead, stderr: &mut dyn Write) -> Result<i32, CliError> {
+    let numbers = input_numbers(options, stdin, stderr)?;
+    if numbers.is_empty() {
+        if options.no_input_error {
+            return Ok(0);
+        }
+        return Err(CliError::usage(USAGE));
+    }
+    // Even a lone 0 is refused: its gcd would be the status 0, which a script would read as success.
+    reject_gcd_of_zero(&numbers)?;
+    let d = fold_gcd(&numbers, options)?;
+    if d > 255 {
+        return Err(CliError::overflow(format!("the gcd is {}, but --exit-code can only give a gcd of at most 255", d)));
+    }
+    Ok(d as i32)
+}
+
+// With --max-value, numbers above max are thrown away before anything is computed, for data with sentinels like u64::MAX mixed in.
+// retain keeps the elements the closure says yes to, in their original order. Saying how many went keeps a typo in N from quietly emptying the input.
+fn drop_above(numbers: &mut Vec<u64>, max: u64, stderr: &mut dyn Write) {
+    let before = numbers.len();
+    numbers.retain(|n| *n <= max);
+    let dropped = before - numbers.len();
+    if dropped > 0 {
+        writeln!(stderr, "dropped {} of {} numbers above --max-value {}", dropped, before, max).unwrap();
+    }
+}
+
+// With --fail-on-zero a zero anywhere in the input is an error up front, whatever any later step would have made of it, for callers who want the strict
+// contract that every number is positive spelled out. Counting from 1 says which number it was, for a long list where the 0 came from who knows where.
+fn reject_zero(numbers: &[u64]) -> Result<(), CliError> {
+    match numbers.iter().position(|n| *n == 0) {
+        Some(index) => Err(CliError::parse(format!("number {} of the input is 0, which --fail-on-zero doesn't allow", index + 1))),
+        None => Ok(()),
+    }
+}
+
+// gcd isn't defined for 0, so a 0 in the numbers is a parse error, the same as everywhere else that reads numbers to take the gcd of. Counting from 1 says which one it was.
+fn reject_gcd_of_zero(numbers: &[u64]) -> Result<(), CliError> {
+    match numbers.iter().position(|n| *n == 0) {
+        Some(index) => Err(CliError::parse(format!("number {} of the input is 0, and gcd isn't defined for 0", index + 1))),
+        None => Ok(()),
+    }
+}
+
+// Gathers the numbers from every source in the order they were given.
+fn collect_numbers(options: &Options, stdin: &mut dyn BufRead) -> Result<Vec<u64>, CliError> {
+    collect_from(&options.sources, options, stdin)
+}
+
+// The same for just some of the sources, like one group of them between two --.
+fn collect_from(sources: &[Source], options: &Options, stdin: &mut dyn BufRead) -> Result<Vec<u64>, CliError> {
     // We create a mutable variable set to a new Vec or vector which is the same as Python's list or JS's array. We must make the variable mut even though Vec is designed to be modified do to Rust's inherent vars are immutable unless stated otherwise.
-    // We don't need to specify u64 here because Rust will infer it via gcd because that function only accepts u64 values.
+    // We don't need to specify u64 here because Rust will infer it from the function's return type.
     let mut numbers = Vec::new();
 
-    // Here is a standard for loop in which the var arg takes on the arguments being looped over
-    // std::env::args returns an iterator, a value that produces each argument on demand, and indicates when we're done. Iterators are things that'd we normally want to loop over and Rust's standard library contains many types.
-    // The first value produced by std::... is the name of the program being run, so we skip() over it.
-    for arg in std::env::args().skip(1) {
-        // Here we call u64::from_str to attempt to parse our command-line argument arg as an unsigned 64-bit integer. The from_str function doesn't return a u64 directly, but rather a Result value that indicates whether the parse succeeded or failed. A Result Value is one of two variants:
-        // Ok(v), indicating that the parse succeeded and v is the value produced.
-        // Err(e), indicating that the parse failed and e is an error value explaining why.
-        // Rust does not have exceptions: all errors are handled using either Result or panic.
-        // We check the success of our parse by using the expect method. If an error, expect prints a msg that includes a description of e, and exists the program immediately. However if Ok(v), expect returns v itself, which we push onto the end of our vector of numbers.
-        numbers.push(u64::from_str(&arg).expect("error parsing argument"));
+    for source in sources {
+        match source {
+            Source::Arg(arg) if options.from_ranges && arg.contains("..") => {
+                numbers.extend(parse_range(arg, options.number_format(options.input_base))?);
+            }
+            // An argument like 42|56 is several numbers once --input-sep has split it.
+            Source::Arg(arg) if options.input_sep.is_some() => {
+                parse_tokens(arg, options.number_format(options.input_base), &mut numbers)?
+            }
+            Source::Arg(arg) => numbers.push(parse_number(arg, options.number_format(options.input_base))?),
+            Source::File(path) => read_numbers_from_file(path, options.number_format(options.input_base), &mut numbers)?,
+            Source::Glob(pattern) => {
+                for path in expand_glob(pattern)? {
+                    read_numbers_from_file(&path, options.number_format(options.input_base), &mut numbers)?;
+                }
+            }
+            Source::Csv(path) => read_numbers_from_csv(path, options, &mut numbers)?,
+            Source::Exec(command) => {
+                parse_tokens(&run_command(command)?, options.number_format(options.input_base), &mut numbers)?
+            }
+            Source::Seeded { count, seed } => numbers.extend(seeded_numbers(*count, *seed)),
+            // Without --stdin-radix, numbers on stdin are read in the same base as everything else.
+            Source::Stdin => {
+                let mut contents = String::new();
+                stdin
+                    .read_to_string(&mut contents)
+                    .map_err(|e| CliError::io(format!("error reading stdin: {}", e)))?;
+                let radix = options.stdin_radix.unwrap_or(options.input_base);
+                parse_tokens(&contents, options.number_format(radix), &mut numbers)?;
+            }
+        }
+    }
+
+    Ok(numbers)
+}
+
+// With --dry-run the inputs are read and checked but nothing is computed, as a quick check that a big file is in the right format.
+// Each source is read on its own, so one bad file doesn't stop the rest being checked: the count of good numbers goes to stdout, and the first problem in each source
+// is listed in the error, which makes the exit status a parse error as usual.
+fn dry_run(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write) -> Result<(), CliError> {
+    let mut count = 0;
+    let mut problems = Vec::new();
+    for index in 0..options.sources.len() {
+        match collect_from(&options.sources[index..index + 1], options, stdin) {
+            Ok(numbers) => count += numbers.len(),
+            Err(error) => problems.push(error),
+        }
+    }
+    let plural = if count == 1 { "" } else { "s" };
+    if problems.is_empty() {
+        writeln!(stdout, "Read {} number{} with no errors; nothing was computed", count, plural).unwrap();
+        return Ok(());
+    }
+    writeln!(stdout, "Read {} number{}, but {} of the inputs had errors; nothing was computed", count, plural, problems.len()).unwrap();
+    // Missing files and bad numbers have different exit statuses, so the first problem's is the one used, along with every message.
+    let code = problems[0].code;
+    let messages: Vec<String> = problems.into_iter().map(|error| error.message).collect();
+    Err(CliError { code, message: messages.join("\n") })
+}
+
+// With -- between them, gcd 12 18 -- 6 30 has two groups of numbers, and checks that they have the same gcd, printing each group's result as usual.
+// When they all match the exit status is 0; when they don't, it's a failed check listing each group's gcd.
+// The results are joined with --newline or --space like any other list of results, except with --ndjson, which always has one object to a line.
+fn compare_groups(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write) -> Result<(), CliError> {
+    if !matches!(options.command, Command::Gcd) {
+        return Err(CliError::usage("groups separated by -- can only be compared with plain gcd"));
+    }
+    let mut gcds = Vec::new();
+    let mut results = Vec::new();
+    for numbers in collect_groups(options, stdin)? {
+        let d = gcd_all(&numbers).unwrap();
+        let outcome = GcdOutcome { numbers: &numbers, gcd: d };
+        if options.ndjson {
+            write_ndjson(&outcome, stdout);
+        } else {
+            results.push(options.formatter.format(&outcome));
+        }
+        gcds.push(d);
+    }
+    if !options.ndjson {
+        writeln!(stdout, "{}", results.join(options.separator)).unwrap();
+    }
+    if gcds.iter().any(|d| *d != gcds[0]) {
+        return Err(CliError::check_failed(format!("the groups have different greatest common divisors: {}", list_text(&gcds, false))));
+    }
+    Ok(())
+}
+
+// The compare subcommand takes exactly two groups, gcd compare 12 18 -- 8 12, and prints how the first group's gcd compares with the second's: >, since 6 > 4.
+fn print_ordering(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write) -> Result<(), CliError> {
+    let groups = collect_groups(options, stdin)?;
+    let (a, b) = match groups.as_slice() {
+        [a, b] => (a, b),
+        _ => return Err(CliError::usage("Usage: gcd compare NUMBER ... -- NUMBER ...")),
+    };
+    // collect_groups has already refused empty groups, so there's always an ordering.
+    let symbol = match compare_gcds(a, b).unwrap() {
+        Ordering::Less => "<",
+        Ordering::Equal => "=",
+        Ordering::Greater => ">",
+    };
+    writeln!(stdout, "{}", symbol).unwrap();
+    Ok(())
+}
+
+// Reads the numbers in each group between the -- separators. The breaks are where each group ends, and the last group runs to the end of the sources.
+// An empty group or one with a 0 in it can't have a gcd to compare, so either is an error naming the group.
+fn collect_groups(options: &Options, stdin: &mut dyn BufRead) -> Result<Vec<Vec<u64>>, CliError> {
+    let mut ends = options.group_breaks.clone();
+    ends.push(options.sources.len());
+    let mut start = 0;
+    let mut groups = Vec::new();
+    for (index, end) in ends.into_iter().enumerate() {
+        let numbers = collect_from(&options.sources[start..end], options, stdin)?;
+        start = end;
+        if numbers.is_empty() {
+            return Err(CliError::usage(format!("group {} has no numbers in it", index + 1)));
+        }
+        if numbers.contains(&0) {
+            return Err(CliError::parse(format!("group {} contains a 0, and gcd isn't defined for 0", index + 1)));
+        }
+        groups.push(numbers);
+    }
+    Ok(groups)
+}
+
+fn print_gcd(numbers: &[u64], options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    // We check that our numbers vector isn't empty as we don't want to divide by zero, if it is, we want to exit the program.
+    // For wrapper scripts that may legitimately pass nothing, --no-input-error answers 0 instead, the same convention as gcd(0, 0) = 0.
+    // It goes through the formatter like any other answer, so --json still gets a JSON object.
+    if numbers.is_empty() {
+        if options.no_input_error {
+            writeln!(stdout, "{}", options.formatter.format(&GcdOutcome { numbers, gcd: 0 })).unwrap();
+            return Ok(());
+        }
+        return Err(CliError::usage(USAGE));
+    }
+    // Every mode below works out gcds one way or another, so a 0 is turned away here, once, rather than reaching gcd's assert part way through one of them.
+    // A single number is its own gcd without gcd ever being called, so gcd 0 is still 0.
+    if numbers.len() > 1 {
+        reject_gcd_of_zero(numbers)?;
+    }
+
+    // --assert-coprime and --min-gcd are gates for scripts, checked before any of the modes below can print and return, so --explain or --histogram can't slip past them.
+    // When a gate passes, the mode goes ahead as usual.
+    let gate = if options.assert_coprime || options.min_gcd.is_some() { Some(fold_gcd(numbers, options)?) } else { None };
+    // --assert-coprime makes a shared factor an error, so the exit status says whether the numbers were coprime.
+    if let (true, Some(d)) = (options.assert_coprime, gate) {
+        if d != 1 {
+            let list = format_list(numbers, options);
+            return Err(CliError::check_failed(format!("{} are not coprime: they share the common factor {}", list, d)));
+        }
+    }
+    // --min-gcd is the same idea: a gcd below N is an error, and the message says what it actually was.
+    if let (Some(min), Some(d)) = (options.min_gcd, gate) {
+        if d < min {
+            let list = format_list(numbers, options);
+            return Err(CliError::check_failed(format!("the greatest common divisor of {} is {}, less than {}", list, d, min)));
+        }
+    }
+
+    if options.explain {
+        write!(stdout, "{}", explain(numbers)).unwrap();
+        if options.verbose {
+            writeln!(stdout, "{}", summary(numbers)).unwrap();
+        }
+        return Ok(());
+    }
+
+    if options.trace_json {
+        writeln!(stdout, "{}", trace(numbers)).unwrap();
+        return Ok(());
+    }
+
+    if options.explain_json {
+        let (steps, d) = fold_steps(numbers);
+        writeln!(stdout, "{}", explain_json(numbers, &steps, d)).unwrap();
+        return Ok(());
+    }
+
+    if options.tree_output {
+        let tree = gcd_tree(numbers).unwrap();
+        write!(stdout, "{}", render_tree(&tree, "", "")).unwrap();
+        return Ok(());
+    }
+
+    if options.histogram {
+        write!(stdout, "{}", histogram(numbers)?).unwrap();
+        return Ok(());
+    }
+
+    if options.factor_common {
+        // Only a lone 0 gets this far, and its gcd, 0 itself, has no prime factors to list.
+        if numbers == [0] {
+            return Err(CliError::usage("--factor-common can't factor 0"));
+        }
+        writeln!(stdout, "{}", common_factorization(fold_gcd(numbers, options)?)).unwrap();
+        return Ok(());
+    }
+
+    // Like --assert-coprime, but every pair has to be coprime, not just the whole set.
+    if options.pairwise_coprime {
+        if !pairwise_coprime(numbers) {
+            return Err(CliError::check_failed(format!("{} are not pairwise coprime", format_list(numbers, options))));
+        }
+        writeln!(stdout, "{} are pairwise coprime", format_list(numbers, options)).unwrap();
+        return Ok(());
     }
 
-    // We check that the length of our numbers vector isn't 0 as we don't want to divide by zero, if it does, we want to exit the program.
-    if numbers.len() == 0 {
-        // writeLn! macro allows us to write  our error message to the standard error output stream provided by std::io:stderr().
-        // The .unwrap() call is a terse way to check that the attempt to print the error msg did not itself fail.
-        writeln!(std::io::stderr(), "Usage: gcd NUMBER ...").unwrap();
-        std::process::exit(1);
+    if options.coprime_count {
+        writeln!(stdout, "{}", coprime_count(numbers)?).unwrap();
+        return Ok(());
     }
 
+    // Long inputs are split across threads. --max-iterations guards each step of the sequential fold, so it always takes that path.
+    // The parallel code uses Euclid's algorithm, so asking for another one with --algorithm takes the sequential path too.
+    let calculator = GcdCalculator { parallel_threshold: options.parallel_threshold, dedup: options.dedup };
+    // The progress bar follows the sequential fold too.
+    let sequential = options.max_iterations.is_some()
+        || options.algorithm.is_some_and(|a| a != Algorithm::Euclid)
+        || options.verify
+        || options.progress;
+    // A gate has already worked the gcd out, so it isn't folded a second time.
+    let d = match gate {
+        Some(d) => d,
+        None if !sequential && calculator.uses_parallel(numbers.len()) => calculator.gcd_all(numbers).unwrap(),
+        None => fold_gcd(numbers, options)?,
+    };
+
+    // Without another mode, a passed --assert-coprime says so in words.
+    if options.assert_coprime {
+        writeln!(stdout, "{} are coprime", format_list(numbers, options)).unwrap();
+        return Ok(());
+    }
+
+    writeln!(stdout, "{}", options.formatter.format(&GcdOutcome { numbers, gcd: d })).unwrap();
+    Ok(())
+}
+
+// How the input list is echoed back in messages, honouring --bare-list.
+fn format_list(numbers: &[u64], options: &Options) -> String {
+    list_text(numbers, options.bare_list)
+}
+
+// Folds gcd across numbers, which mustn't be empty, one number at a time.
+fn fold_gcd(numbers: &[u64], options: &Options) -> Result<u64, CliError> {
+    // Without --algorithm, the library's default is whichever is quicker.
+    let algorithm = options.algorithm.unwrap_or_default();
+    let mut progress = ProgressBar::new(options.progress, numbers.len());
     // Making var d mutable as its value will change. Initially setting it to the first value of the vector.
     let mut d = numbers[0];
     // The & operator borrows a reference to the vector's elements from the second onward. The for loop iterates over the referenced elements, letting m borrow each element in succession.
     for m in &numbers[1..] {
+        // --verify works every step out a second way and stops if the two ever disagree. That would mean a bug in one of them, so the message gives everything needed to reproduce it.
+        if options.verify {
+            let (euclid, binary) = (gcd(d, *m), binary_gcd(d, *m));
+            if euclid != binary {
+                return Err(CliError::check_failed(format!(
+                    "verification failed for gcd({}, {}): Euclid's algorithm gave {} but the binary algorithm gave {}",
+                    d, m, euclid, binary
+                )));
+            }
+        }
         // The * operator dereferences m, yielding the value it refers to; this is the next u64 we want to pass to gcd. This will be explained in detail in later chapters. But essentially:
         // &x borrows a reference to x, and that *r is the value that the reference r refers to.
-        // Since numbers owns the vector, Rust automatically frees it when numbers goes out of scope at the end of main.
-        d = gcd(d, *m);
+        d = match options.max_iterations {
+            Some(max) => gcd_bounded(d, *m, max)
+                .ok_or_else(|| CliError::check_failed(format!("gcd({}, {}) did not finish within {} iterations", d, m, max)))?,
+            None => algorithm.gcd(d, *m),
+        };
+        progress.tick();
     }
+    progress.finish();
+    Ok(d)
+}
 
-    println!("The greatest common divisor of {:?} is {}", numbers, d);
+// Progress
 
-    // Rust assumes that if main returns at all, the program finished successfully. Only by explicitly calling functions like expect or std::process::exit can we cause the program to terminate with an error status code.
+// How many numbers go by between redraws of the progress bar. Drawing is far slower than a gcd step, so doing it every time would slow the fold right down.
+const PROGRESS_EVERY: usize = 10_000;
 
-    // We can run the program from cmd line cargo run 42 56 or 42 56 80, or 42, or none at all.
+// A progress bar on stderr for --progress, so long reductions show they're still going. The results go to stdout, so the bar never ends up mixed into them.
+// It's only drawn when stderr is a terminal: redirected to a file, a stream of \r-separated redraws would just be noise.
+struct ProgressBar {
+    stderr: Option<std::io::Stderr>,
+    done: usize,
+    total: usize,
 }
 
-// A Simple Function (part 1)
+impl ProgressBar {
+    fn new(enabled: bool, total: usize) -> ProgressBar {
+        let stderr = if enabled { terminal_stderr() } else { None };
+        ProgressBar { stderr, done: 1, total }
+    }
+
+    // Called once per number folded in. The first number is counted from the start, since it needs no work.
+    fn tick(&mut self) {
+        self.done += 1;
+        if self.done.is_multiple_of(PROGRESS_EVERY) {
+            self.draw();
+        }
+    }
 
-// The arrow (token) precedes the return type. Our function returns a u64 value.
-fn gcd(mut n: u64, mut m: u64) -> u64 {
-    // assert! is a marco that verifies that neither argument is zero. The ! character marks this as a macro invocation, not a function call. assert! checks that its argument is true, and if it is not, terminates the program (called a panic).
-    assert!(n != 0 && m != 0);
-    while m != 0 {
-        if m < n {
-            // Rust only infers type within function bodies. We must write out the types of function parameters (as above) and return values (as above).
-            // If we wanted to specify t, let t: u64 = m;
-            let t = m;
-            m = n;
-            n = t;
+    // \r moves back to the start of the line, so each redraw covers the last one.
+    fn draw(&mut self) {
+        if let Some(stderr) = &mut self.stderr {
+            let width = 30;
+            let filled = self.done * width / self.total;
+            let bar = "#".repeat(filled) + &".".repeat(width - filled);
+            write!(stderr, "\r[{}] {} of {}", bar, self.done, self.total).unwrap();
         }
-        m = m % n;
     }
-    // Rust has a return statement, but we don't need one here. If a function body ends with an expression that is NOT followed by a semicolon, that's the function's return value. In fact, any block surrounded by curly braces can function as an expression. Ex:
-    // {
-    //     println!("evaluating cos x");
-    //     x.cos()
-    // }
-    // The above is an expression that prints a message then yields x.cos() as it's value.
-    // It's typical in Rust to use this form to establish the function's value when control "falls off the end" of the function, and use return statements only for explicit early returns from the midst of a function.
-    n
+
+    // Draws the bar full and moves on to a new line, so whatever comes next doesn't start halfway along the bar.
+    fn finish(&mut self) {
+        self.draw();
+        if let Some(stderr) = &mut self.stderr {
+            writeln!(stderr).unwrap();
+        }
+    }
+}
+
+// IsTerminal is how the standard library tells a terminal apart from a file or pipe. Without the progress feature there's never a bar to draw.
+#[cfg(feature = "progress")]
+fn terminal_stderr() -> Option<std::io::Stderr> {
+    use std::io::IsTerminal;
+    let stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        Some(stderr)
+    } else {
+        None
+    }
 }
 
-// Writing and Running Unit Tests (part 2)
+#[cfg(not(feature = "progress"))]
+fn terminal_stderr() -> Option<std::io::Stderr> {
+    None
+}
 
-// The below definition mark test_gcd as a test function, to be skipped in normal compilations, but included and called automatically if we run our program with cargo test.
-// #[test] is called an attribute. Attributes are an open-ended system for marking functions and other declarations with extra info. They're used to control compiler warnings and code style checks, include code conditionally, tell Rust how to interact with code written in other languages, etc.
-#[test]
-// Defining a function which calls gcd and checks that it returns correct values.
-fn test_gcd() {
-    assert_eq!(gcd(14, 15), 1);
+// With --lines, every line of stdin is its own list of numbers, and we print one gcd per line. Blank lines are skipped.
+// With --ndjson each answer is written as its own JSON object as soon as its line has been read, so a slow producer upstream doesn't hold everything back until stdin ends.
+// Normally the first line that can't be computed stops everything. With --keep-going that line's answer is the word error instead, the reason goes to stderr as "line N: ...",
+// and only after the last line does the program exit with a parse error saying how many lines failed. With --ndjson the reason is written in place, as {"line":N,"error":"..."}, the same as --batch-file.
+fn print_lines(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write, stderr: &mut dyn Write) -> Result<(), CliError> {
+    if !options.sources.is_empty() {
+        return Err(CliError::usage("--lines reads its numbers from stdin and can't be combined with other inputs"));
+    }
+
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for (index, line) in stdin.lines().enumerate() {
+        let line = line.map_err(|e| CliError::io(format!("error reading stdin: {}", e)))?;
+        let mut numbers = Vec::new();
+        let d = match line_gcd(&line, options, &mut numbers) {
+            Ok(d) => d,
+            Err(error) if options.keep_going => {
+                failed += 1;
+                if options.ndjson {
+                    writeln!(stdout, "{{\"line\":{},\"error\":{}}}", index + 1, json_string(&error.message)).unwrap();
+                    stdout.flush().unwrap();
+                } else {
+                    writeln!(stderr, "line {}: {}", index + 1, error.message).unwrap();
+                    results.push("error".to_string());
+                }
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        if let Some(d) = d {
+            if options.ndjson {
+                write_ndjson(&GcdOutcome { numbers: &numbers, gcd: d }, stdout);
+            } else {
+                results.push(d.to_string());
+            }
+        }
+    }
+    if !options.ndjson {
+        writeln!(stdout, "{}", results.join(options.separator)).unwrap();
+    }
+    if failed > 0 {
+        return Err(CliError::parse(format!("{} of the lines of stdin couldn't be computed", failed)));
+    }
+    Ok(())
+}
+
+// The gcd of one --lines line, leaving the numbers it read in numbers. A blank line has no gcd, which is Ok(None).
+fn line_gcd(line: &str, options: &Options, numbers: &mut Vec<u64>) -> Result<Option<u64>, CliError> {
+    parse_tokens(line, options.number_format(options.input_base), numbers)?;
+    if options.fail_on_zero {
+        reject_zero(numbers)?;
+    }
+    if numbers.contains(&0) {
+        return Err(CliError::parse("gcd isn't defined for 0"));
+    }
+    Ok(gcd_all(numbers))
+}
+
+// With --batch-file PATH, every line of the file is its own gcd problem, and line N of the output is the answer to line N of the file.
+// To keep the lines matched up, a blank line in the file gives a blank line in the output rather than being skipped, and a line that can't be computed
+// gives "line N: " and the reason in its place. The rest of the batch still runs, and only at the end does the program exit with a parse error saying how many lines failed.
+// With --ndjson every object stands on its own, so blank lines are skipped instead, and a failed line is {"line":N,"error":"..."}.
+// That means a batch always keeps going past a bad line; --keep-going is accepted here too, so a script can pass it to both line-by-line modes.
+fn print_batch(path: &str, options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    if !options.sources.is_empty() {
+        return Err(CliError::usage("--batch-file reads its numbers from PATH and can't be combined with other inputs"));
+    }
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| CliError::io(format!("error reading {}: {}", path, e)))?;
+
+    let mut failed = 0;
+    // enumerate counts from 0, and editors count lines from 1.
+    for (index, line) in contents.lines().enumerate() {
+        let mut numbers = Vec::new();
+        let result = match parse_tokens(line, options.number_format(options.input_base), &mut numbers) {
+            Err(error) => Err(error.message),
+            Ok(()) if numbers.contains(&0) => Err("gcd isn't defined for 0".to_string()),
+            Ok(()) => Ok(gcd_all(&numbers)),
+        };
+        match result {
+            Ok(Some(d)) if options.ndjson => write_ndjson(&GcdOutcome { numbers: &numbers, gcd: d }, stdout),
+            Ok(Some(d)) => writeln!(stdout, "{}", d).unwrap(),
+            Ok(None) if options.ndjson => {}
+            Ok(None) => writeln!(stdout).unwrap(),
+            Err(message) => {
+                failed += 1;
+                if options.ndjson {
+                    writeln!(stdout, "{{\"line\":{},\"error\":{}}}", index + 1, json_string(&message)).unwrap();
+                    stdout.flush().unwrap();
+                } else {
+                    writeln!(stdout, "line {}: {}", index + 1, message).unwrap();
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(CliError::parse(format!("{} of the lines in {} couldn't be computed", failed, path)));
+    }
+    Ok(())
+}
+
+// Writes one NDJSON line and flushes it, so whoever is reading gets each result the moment it's ready rather than when a buffer happens to fill.
+fn write_ndjson(outcome: &GcdOutcome, stdout: &mut dyn Write) {
+    writeln!(stdout, "{}", Ndjson.format(outcome)).unwrap();
+    stdout.flush().unwrap();
+}
+
+// The content subcommand takes a polynomial's coefficients, which can be negative, so unlike everywhere else the arguments are signed.
+// They're read as a - and then an ordinary number, so 0x and underscores work the same as anywhere else.
+fn print_content(options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    let mut coeffs = Vec::new();
+    for source in &options.sources {
+        let token = match source {
+            Source::Arg(token) => token,
+            _ => return Err(CliError::usage("content only takes coefficients given as arguments")),
+        };
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token.as_str()),
+        };
+        let magnitude = parse_number(digits, options.number_format(options.input_base))? as i128;
+        let value = if negative { -magnitude } else { magnitude };
+        // An i128 has room for any u64 with either sign, so it's only the conversion down to i64 that can fail.
+        let coeff = i64::try_from(value)
+            .map_err(|_| CliError::overflow(format!("coefficient {:?} doesn't fit in an i64", token)))?;
+        coeffs.push(coeff);
+    }
+    if coeffs.is_empty() {
+        return Err(CliError::usage("Usage: gcd content COEFFICIENT ..."));
+    }
+    writeln!(stdout, "The content of {:?} is {}", coeffs, content(&coeffs)).unwrap();
+    Ok(())
+}
+
+// The reduce subcommand writes each fraction NUM/DEN in lowest terms: gcd reduce 6/8 10/4 prints 6/8 = 3/4 and 10/4 = 5/2.
+// Normally the first bad fraction stops it, after the ones before it have been printed. With --validate-fraction every fraction is checked before any is reduced,
+// and all the problems are reported together, numbered by position, so nothing is printed unless the whole list is good.
+fn print_reduced(options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    let mut tokens = Vec::new();
+    for source in &options.sources {
+        match source {
+            Source::Arg(token) => tokens.push(token),
+            _ => return Err(CliError::usage("reduce only takes fractions given as arguments")),
+        }
+    }
+    if tokens.is_empty() {
+        return Err(CliError::usage("Usage: gcd reduce NUM/DEN ..."));
+    }
+    let format = options.number_format(options.input_base);
+    if options.validate_fraction {
+        let problems: Vec<String> = tokens
+            .iter()
+            .enumerate()
+            .filter_map(|(index, token)| parse_fraction(token, format).err().map(|e| format!("fraction {}: {}", index + 1, e.message)))
+            .collect();
+        if !problems.is_empty() {
+            return Err(CliError::parse(problems.join("\n")));
+        }
+    }
+    for token in tokens {
+        let (num, den) = parse_fraction(token, format)?;
+        let (num, den) = reduce_fraction(num, den);
+        writeln!(stdout, "{} = {}/{}", token, num, den).unwrap();
+    }
+    Ok(())
+}
+
+// Splits NUM/DEN at the slash and parses both halves as ordinary numbers. A zero denominator is refused here, since reduce_fraction would hand it back unchanged.
+fn parse_fraction(token: &str, format: NumberFormat) -> Result<(u64, u64), CliError> {
+    let (num, den) = token.split_once('/').ok_or_else(|| CliError::parse(format!("{:?} is not a fraction like 6/8", token)))?;
+    let num = parse_number(num, format)?;
+    let den = parse_number(den, format)?;
+    if den == 0 {
+        return Err(CliError::parse(format!("{:?} has a zero denominator", token)));
+    }
+    Ok((num, den))
+}
+
+// With --as-fraction, each argument is a decimal like 0.375 and is printed as a fraction in lowest terms, 3/8.
+fn print_fractions(options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    let mut tokens = Vec::new();
+    for source in &options.sources {
+        match source {
+            Source::Arg(token) => tokens.push(token),
+            _ => return Err(CliError::usage("--as-fraction only takes decimals given as arguments")),
+        }
+    }
+    if tokens.is_empty() {
+        return Err(CliError::usage("Usage: gcd --as-fraction DECIMAL ..."));
+    }
+    for token in tokens {
+        let (num, den) = decimal_to_fraction(token)?;
+        writeln!(stdout, "{} = {}/{}", token, num, den).unwrap();
+    }
+    Ok(())
+}
+
+// 0.375 is 375/1000: the digits after the point, over 10 to the power of how many there are. reduce_fraction then divides out their gcd.
+// Only plain digits with at most one point are accepted. Something like 1.2.3, a bare ".", a sign or an exponent is ambiguous enough that we refuse it.
+fn decimal_to_fraction(token: &str) -> Result<(u64, u64), CliError> {
+    let invalid = || CliError::parse(format!("{:?} is not a decimal like 0.375", token));
+    let (whole, decimals) = token.split_once('.').unwrap_or((token, ""));
+    let all_digits = |text: &str| text.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && decimals.is_empty()) || !all_digits(whole) || !all_digits(decimals) {
+        return Err(invalid());
+    }
+
+    let too_precise = || CliError::overflow(format!("{:?} has too many digits to fit in a u64 fraction", token));
+    // 10^19 is the largest power of ten that fits in a u64.
+    let den = 10u64.checked_pow(decimals.len() as u32).ok_or_else(too_precise)?;
+    let digits = format!("{}{}", whole, decimals);
+    let num: u64 = digits.parse().map_err(|_| too_precise())?;
+    Ok(reduce_fraction(num, den))
+}
+
+// Prints several results on one go, separated by --newline (the default) or --space, always finishing with a newline.
+fn write_results(results: &[u64], separator: &str, stdout: &mut dyn Write) {
+    let text: Vec<String> = results.iter().map(|result| result.to_string()).collect();
+    writeln!(stdout, "{}", text.join(separator)).unwrap();
+}
+
+// The most numbers --coprime-count takes. n numbers make n(n-1)/2 pairs, so 10,000 of them is already about 50 million gcds, a few seconds' work.
+const MAX_COPRIME_COUNT_LEN: usize = 10_000;
+
+// With --coprime-count, how many of the pairs of numbers don't share a factor, out of every way of picking two: 4 of 6 pairs are coprime (66.7%).
+// Unlike --pairwise-coprime this can't stop at the first pair that shares a factor, so every pair gets its own gcd and the time grows with the square of the input.
+// Equal numbers at different positions still count as a pair.
+fn coprime_count(numbers: &[u64]) -> Result<String, CliError> {
+    if numbers.len() > MAX_COPRIME_COUNT_LEN {
+        return Err(CliError::usage(format!(
+            "--coprime-count compares every pair, so it takes at most {} numbers, not {}",
+            MAX_COPRIME_COUNT_LEN,
+            numbers.len()
+        )));
+    }
+    let mut pairs = 0u64;
+    let mut coprime = 0u64;
+    for (i, n) in numbers.iter().enumerate() {
+        // Only the numbers after n, so each unordered pair is looked at once.
+        for m in &numbers[i + 1..] {
+            pairs += 1;
+            let shared = if *n == 0 || *m == 0 { n | m } else { gcd(*n, *m) };
+            if shared == 1 {
+                coprime += 1;
+            }
+        }
+    }
+    if pairs == 0 {
+        return Ok("There are no pairs to compare in a single number".to_string());
+    }
+    let percent = coprime as f64 * 100.0 / pairs as f64;
+    Ok(format!("{} of {} pairs are coprime ({:.1}%)", coprime, pairs, percent))
+}
+
+// The primes every number shares, each to the smallest power it appears to in any of them, multiplied out: 360 and 84 give 2^2 * 3 = 12.
+// That product is the gcd, so rather than factor every number and compare, factoring the gcd gives the same primes and exponents in one go.
+// With no primes in common the product is empty, which is 1.
+fn common_factorization(d: u64) -> String {
+    let factors: Vec<String> = prime_factors(d)
+        .iter()
+        .map(|&(p, exponent)| if exponent == 1 { p.to_string() } else { format!("{}^{}", p, exponent) })
+        .collect();
+    if factors.is_empty() {
+        return "1 = 1".to_string();
+    }
+    format!("{} = {}", factors.join(" * "), d)
+}
+
+// For each prime that divides any of the numbers, counts how many of the numbers it divides. The most widely shared primes are listed first; a prime shared by all of them is a factor of the gcd.
+fn histogram(numbers: &[u64]) -> Result<String, CliError> {
+    // A BTreeMap keeps its keys in sorted order, so the primes come out smallest first on every run, unlike a HashMap whose order can change from one run to the next.
+    let mut counts: BTreeMap<u64, usize> = BTreeMap::new();
+    // A number that's repeated is still counted every time, but only factored the first time.
+    let mut cache = FactorCache::new();
+    for n in numbers {
+        if *n == 0 {
+            return Err(CliError::usage("--histogram can't factor 0"));
+        }
+        for (prime, _) in cache.factors(*n) {
+            *counts.entry(*prime).or_insert(0) += 1;
+        }
+    }
+
+    // Then sort by count, from most to least; Reverse flips the comparison. sort_by_key is stable, so primes with the same count stay smallest first.
+    let mut rows: Vec<(u64, usize)> = counts.into_iter().collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.1));
+
+    let mut text = String::new();
+    for (prime, count) in rows {
+        text += &format!("{}: {} of {}\n", prime, count, numbers.len());
+    }
+    Ok(text)
+}
+
+// Draws the tree top-down with the root's gcd first and each child indented under its parent:
+// 2
+// |-- 6
+// |   |-- 12
+// |   `-- 18
+// ...
+// first is the connector drawn before this node's own value, and rest is what goes in front of every line below it.
+fn render_tree(tree: &GcdTree, first: &str, rest: &str) -> String {
+    let mut text = format!("{}{}\n", first, tree.gcd());
+    if let GcdTree::Node { left, right, .. } = tree {
+        text += &render_tree(left, &format!("{}|-- ", rest), &format!("{}|   ", rest));
+        text += &render_tree(right, &format!("{}`-- ", rest), &format!("{}    ", rest));
+    }
+    text
+}
+
+// Describes, one sentence per division, how the gcd was found. With more than two numbers, each pair in the fold gets its own indented explanation.
+fn explain(numbers: &[u64]) -> String {
+    match numbers {
+        [n] => format!("There is only one number, so the greatest common divisor is {} itself.\n", n),
+        [n, m] => explain_pair(*n, *m, ""),
+        _ => {
+            let mut text = format!("Folding gcd over {:?} one number at a time.\n", numbers);
+            let mut d = numbers[0];
+            for m in &numbers[1..] {
+                text += &format!("gcd({}, {}):\n", d, m);
+                text += &explain_pair(d, *m, "  ");
+                d = gcd(d, *m);
+            }
+            text + &format!("The greatest common divisor of {:?} is {}.\n", numbers, d)
+        }
+    }
+}
+
+// The same divisions --explain describes, as JSON for --trace-json, so a program can draw them instead of a person reading them.
+fn trace(numbers: &[u64]) -> String {
+    let (steps, d) = fold_steps(numbers);
+    trace_json(numbers, &steps, d)
+}
+
+// Every division made folding gcd over the numbers, in order, along with the gcd they end at. --trace-json and --explain-json both describe these.
+fn fold_steps(numbers: &[u64]) -> (Vec<GcdStep>, u64) {
+    let mut d = numbers[0];
+    let mut steps = Vec::new();
+    for m in &numbers[1..] {
+        steps.extend(gcd_steps(d, *m));
+        d = gcd(d, *m);
+    }
+    (steps, d)
+}
+
+// One line totting up the explanation: how many numbers there were, how many divisions it took, and the answer.
+// The steps are counted from the same gcd_steps calls the explanation prints, one per "Reduced" line.
+fn summary(numbers: &[u64]) -> String {
+    let mut d = numbers[0];
+    let mut steps = 0;
+    for m in &numbers[1..] {
+        steps += gcd_steps(d, *m).len();
+        d = gcd(d, *m);
+    }
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    format!("Processed {} number{} in {} reduction step{}; GCD = {}", numbers.len(), plural(numbers.len()), steps, plural(steps), d)
+}
+
+// A String can be built up with +=, which appends to it in place.
+fn explain_pair(n: u64, m: u64, indent: &str) -> String {
+    let steps = gcd_steps(n, m);
+    let mut text = String::new();
+    for step in &steps {
+        text += &format!(
+            "{}Reduced {} and {}: {} = {}*{} + {}, remainder {}.\n",
+            indent, step.a, step.b, step.a, step.quotient, step.b, step.remainder, step.remainder
+        );
+    }
+    // gcd_steps always records at least one step, and the divisor in the last one is the answer.
+    let g = steps[steps.len() - 1].b;
+    text + &format!("{}The remainder is 0, so the greatest common divisor of {} and {} is {}.\n", indent, n, m, g)
+}
+
+// Prints the Bezout identity for exactly two numbers, e.g. gcd(240, 46) = 2 = 240*(-9) + 46*47.
+fn print_bezout(numbers: &[u64], stdout: &mut dyn Write) -> Result<(), CliError> {
+    // A slice pattern both checks the length and pulls out the two values in one go.
+    let (a, b) = match numbers {
+        [a, b] => (*a, *b),
+        _ => return Err(CliError::usage("Usage: gcd bezout A B")),
+    };
+
+    let (g, x, y) = extended_gcd(a, b);
+    // The identity should always hold, but check it before claiming it does.
+    if a as i128 * x + b as i128 * y != g as i128 {
+        return Err(CliError::check_failed(format!("internal error: Bezout identity failed for {} and {}", a, b)));
+    }
+
+    writeln!(stdout, "gcd({}, {}) = {} = {}*{} + {}*{}", a, b, g, a, signed(x), b, signed(y)).unwrap();
+    Ok(())
+}
+
+// Prints the nth prime, counting 2 as the first.
+fn print_nth_prime(numbers: &[u64], stdout: &mut dyn Write) -> Result<(), CliError> {
+    match numbers {
+        [0] => Err(CliError::usage("primes are counted from 1, so there is no 0th prime")),
+        [n] => {
+            writeln!(stdout, "{}", nth_prime(*n)).unwrap();
+            Ok(())
+        }
+        _ => Err(CliError::usage("Usage: gcd nthprime N")),
+    }
+}
+
+// The lcm subcommand folds checked_lcm over the numbers the same way print_gcd folds gcd.
+// The lcm grows quickly, so it's easy to go past what a u64 holds; that's an overflow error, and --explain-overflow says where it happened and how big the answer really is.
+fn print_lcm(numbers: &[u64], options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    if numbers.is_empty() {
+        return Err(CliError::usage("Usage: gcd lcm NUMBER ..."));
+    }
+    if numbers.contains(&0) {
+        return Err(CliError::usage("0 has no multiples to share, so it has no lcm"));
+    }
+    let mut l = numbers[0];
+    for m in &numbers[1..] {
+        l = match checked_lcm(l, *m) {
+            Some(l) => l,
+            None => {
+                let mut message = format!("the least common multiple of {} is too large for a u64", format_list(numbers, options));
+                if options.explain_overflow {
+                    message += &explain_lcm_overflow(l, *m);
+                }
+                return Err(CliError::overflow(message));
+            }
+        };
+    }
+    writeln!(stdout, "The least common multiple of {} is {}", format_list(numbers, options), l).unwrap();
+    Ok(())
+}
+
+// lcm(l, m) is worked out as (l / g) * m, and it's that last multiplication that overflowed. Redoing it in u128, which two u64s always fit in, gives the true value and so its size in bits.
+// Later numbers can only make the lcm bigger, so this is a lower bound for the whole list.
+fn explain_lcm_overflow(l: u64, m: u64) -> String {
+    let g = gcd(l, m);
+    let product = (l / g) as u128 * m as u128;
+    let bits = 128 - product.leading_zeros();
+    format!(
+        "\nlcm({}, {}) = ({} / {}) * {} = {} * {} = {}, which needs {} bits, but a u64 only has 64",
+        l, m, l, g, m, l / g, m, product, bits
+    )
+}
+
+// The Interactive Loop
+
+// The repl subcommand reads one list of numbers per line and answers each straight away, until stdin ends or the line is quit.
+// A bad line is reported and the loop carries on, since in an interactive session retyping it is easy but losing the session isn't.
+fn run_repl(options: &Options, stdin: &mut dyn BufRead, stdout: &mut dyn Write) -> Result<(), CliError> {
+    let mut cache = HashMap::new();
+    let mut line = String::new();
+    loop {
+        write!(stdout, "> ").unwrap();
+        // The prompt has no newline, so it has to be flushed out by hand or it won't show up until after the user has typed their answer.
+        stdout.flush().unwrap();
+        line.clear();
+        let read = stdin.read_line(&mut line).map_err(|e| CliError::io(format!("error reading stdin: {}", e)))?;
+        if read == 0 || line.trim() == "quit" {
+            writeln!(stdout).unwrap();
+            return Ok(());
+        }
+
+        let mut numbers = Vec::new();
+        match parse_tokens(&line, options.number_format(options.input_base), &mut numbers) {
+            Err(error) => writeln!(stdout, "{}", error.message).unwrap(),
+            Ok(()) if numbers.is_empty() => {}
+            Ok(()) if numbers.contains(&0) => writeln!(stdout, "gcd isn't defined for 0").unwrap(),
+            Ok(()) => writeln!(stdout, "{}", cached_gcd(&mut cache, &numbers).0).unwrap(),
+        }
+    }
+}
+
+// Remembers the gcd of every list the repl has seen, so typing the same list again is answered without recomputing it.
+// The gcd doesn't depend on the order of the numbers or on repeats, so the key is the list sorted with duplicates removed: 12 18 and 18 12 12 share an entry.
+// The bool in the result says whether the answer came from the cache.
+fn cached_gcd(cache: &mut HashMap<Vec<u64>, u64>, numbers: &[u64]) -> (u64, bool) {
+    let mut key = numbers.to_vec();
+    key.sort_unstable();
+    key.dedup();
+    if let Some(d) = cache.get(&key) {
+        return (*d, true);
+    }
+    let d = gcd_all(&key).unwrap();
+    cache.insert(key, d);
+    (d, false)
+}
+
+// Prints every divisor of N, separated by --newline (the default) or --space.
+fn print_divisors(numbers: &[u64], options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    match numbers {
+        [0] => Err(CliError::usage("every number divides 0, so it has no list of divisors")),
+        [n] => {
+            write_results(&divisors(*n), options.separator, stdout);
+            Ok(())
+        }
+        _ => Err(CliError::usage("Usage: gcd divisors N")),
+    }
+}
+
+// The convert subcommand has nothing to do with gcds: it prints VALUE, read in base --from, in base --to. Both default to 10.
+fn print_conversion(numbers: &[u64], options: &Options, stdout: &mut dyn Write) -> Result<(), CliError> {
+    match numbers {
+        [n] => {
+            writeln!(stdout, "{}", format_u64_radix(*n, options.output_base)).unwrap();
+            Ok(())
+        }
+        _ => Err(CliError::usage("Usage: gcd convert VALUE --from BASE --to BASE")),
+    }
+}
+
+// Wraps negative coefficients in parentheses so 240*(-9) doesn't read as a subtraction.
+fn signed(value: i128) -> String {
+    if value < 0 {
+        format!("({})", value)
+    } else {
+        value.to_string()
+    }
+}
+
+// Here we call parse_u64_token_radix to attempt to parse a token as an unsigned 64-bit integer written in the given base (or the base its 0x, 0o or 0b prefix names). It doesn't return a u64 directly, but rather a Result value that indicates whether the parse succeeded or failed. A Result Value is one of two variants:
+// Ok(v), indicating that the parse succeeded and v is the value produced.
+// Err(e), indicating that the parse failed and e is an error value explaining why.
+// Rust does not have exceptions: all errors are handled using either Result or panic.
+// map_err turns the parse error into a message naming the offending token, which ? then hands back to our caller. A number too big for a u64 counts as an overflow rather than a typo.
+// --strict swaps in a parser that only takes numbers written the one canonical way, plain decimal digits with no leading zeros.
+// With --grouping, thousands separators are checked and taken out first. Numbers are still split up at whitespace before they get here, never at commas,
+// so 1,234 is always one number; --grouping space only helps with an argument quoted as "1 234", since in files and on stdin the space separates two numbers.
+fn parse_number(token: &str, format: NumberFormat) -> Result<u64, CliError> {
+    let parse = |digits: &str| if format.strict { parse_u64_token_strict(digits) } else { parse_u64_token_radix(digits, format.radix) };
+    let parsed = match format.grouping {
+        Some(separator) => strip_grouping(token, separator).and_then(|digits| parse(&digits)),
+        None => parse(token),
+    };
+    parsed.map_err(|e| {
+        let message = format!("error parsing argument {:?}: {}", token, e);
+        match e {
+            ParseTokenError::Overflow => CliError::overflow(message),
+            _ => CliError::parse(message),
+        }
+    })
+}
+
+// The most numbers a single range may expand to, so a typo like 1..10000000000 can't eat all the memory.
+const MAX_RANGE_LEN: u64 = 1_000_000;
+
+// With --from-ranges, an argument can be a range written the same way as in Rust: A..B runs from A up to but not including B, and A..=B includes B.
+// Ranges that go backwards or contain no numbers at all are refused rather than quietly contributing nothing, and so is one starting at 0, since gcd isn't defined for 0.
+fn parse_range(token: &str, format: NumberFormat) -> Result<std::ops::RangeInclusive<u64>, CliError> {
+    // split_once splits at the first "..", leaving "=B" in end for an inclusive range.
+    let (start, end) = token.split_once("..").ok_or_else(|| CliError::parse(format!("invalid range {:?}", token)))?;
+    let (end, inclusive) = match end.strip_prefix('=') {
+        Some(end) => (end, true),
+        None => (end, false),
+    };
+    let start = parse_number(start, format)?;
+    let end = parse_number(end, format)?;
+
+    if start > end || (start == end && !inclusive) {
+        return Err(CliError::parse(format!("range {:?} is empty", token)));
+    }
+    if start == 0 {
+        return Err(CliError::parse(format!("range {:?} contains 0, and gcd isn't defined for 0", token)));
+    }
+    let last = if inclusive { end } else { end - 1 };
+    if last - start >= MAX_RANGE_LEN {
+        return Err(CliError::usage(format!("range {:?} has more than {} numbers", token, MAX_RANGE_LEN)));
+    }
+    Ok(start..=last)
+}
+
+// Parses every whitespace-separated token in text and pushes it onto numbers.
+// With --null-separated (or -0, like xargs) tokens are split at NUL bytes instead, as find -print0 writes them. Whitespace around a token is trimmed, so a trailing newline
+// doesn't get in the way, and the empty token after a final NUL is skipped.
+// With --input-sep the text is also split wherever the pattern matches, so 42|56:98 is three numbers with --input-sep '[|:]'. Whitespace still separates numbers too,
+// which keeps one number per line working, and the empty tokens left by separators next to each other are skipped.
+fn parse_tokens(text: &str, format: NumberFormat, numbers: &mut Vec<u64>) -> Result<(), CliError> {
+    if let Some(pattern) = format.input_sep {
+        for token in pattern.split(text).into_iter().flat_map(|piece| piece.split_whitespace()) {
+            numbers.push(parse_number(token, format)?);
+        }
+        return Ok(());
+    }
+    if format.null_separated {
+        for token in text.split('\0').map(|token| token.trim()).filter(|token| !token.is_empty()) {
+            numbers.push(parse_number(token, format)?);
+        }
+        return Ok(());
+    }
+    for token in text.split_whitespace() {
+        numbers.push(parse_number(token, format)?);
+    }
+    Ok(())
+}
+
+// Runs the command given to --exec and hands back what it printed. The command is split at whitespace into the program and its arguments and run directly,
+// not through a shell, so quotes, pipes and $VARIABLES mean nothing special. Anything needing those can go in a script for --exec to run.
+// output waits for the command to finish and collects its stdout and stderr. A command that fails is an I/O error, passing on what it wrote to stderr.
+fn run_command(command: &str) -> Result<String, CliError> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or_else(|| CliError::usage("--exec needs a command to run"))?;
+    let output = std::process::Command::new(program)
+        .args(words)
+        .output()
+        .map_err(|e| CliError::io(format!("error running {:?}: {}", command, e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CliError::io(format!("{:?} failed with {}: {}", command, output.status, stderr.trim_end())));
+    }
+    String::from_utf8(output.stdout).map_err(|_| CliError::parse(format!("{:?} printed something that isn't UTF-8", command)))
+}
+
+// Reads the numbers in the file at path. Failing to open the file reports which path it was.
+fn read_numbers_from_file(path: &str, format: NumberFormat, numbers: &mut Vec<u64>) -> Result<(), CliError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| CliError::io(format!("error reading {}: {}", path, e)))?;
+    parse_tokens(&contents, format, numbers)
+}
+
+// Reads the numbers in column --column of every record in a CSV file. Spaces around a cell are ignored.
+// With --header the first record holds the column names: it's skipped, and it's where a --column name is looked up.
+// Rows are numbered from 1, the way a spreadsheet shows them, counting the header, so an error says exactly where to look.
+#[cfg(feature = "csv")]
+fn read_numbers_from_csv(path: &str, options: &Options, numbers: &mut Vec<u64>) -> Result<(), CliError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| CliError::io(format!("error reading {}: {}", path, e)))?;
+    let records = csv::parse_csv(&contents).map_err(|e| CliError::parse(format!("error reading {}: {}", path, e)))?;
+    let skip = if options.csv_header { 1 } else { 0 };
+    let column = match &options.csv_column {
+        Column::Index(index) => *index,
+        Column::Name(name) => records
+            .first()
+            .and_then(|header| header.iter().position(|field| field.trim() == name))
+            .ok_or_else(|| CliError::parse(format!("{} has no column named {:?}", path, name)))?,
+    };
+    for (i, record) in records.iter().enumerate().skip(skip) {
+        let row = i + 1;
+        let cell = record
+            .get(column)
+            .ok_or_else(|| CliError::parse(format!("{} row {} has no column {}", path, row, column)))?;
+        let n = parse_number(cell.trim(), options.number_format(options.input_base))
+            .map_err(|e| CliError { message: format!("{} row {}: {}", path, row, e.message), ..e })?;
+        numbers.push(n);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "csv"))]
+fn read_numbers_from_csv(_path: &str, _options: &Options, _numbers: &mut Vec<u64>) -> Result<(), CliError> {
+    Err(CliError::usage("--csv requires building with `--features csv`"))
+}
+
+// Expands a pattern like data/*.txt into the matching file paths, sorted so the files are always read in the same order.
+// Only the last part of the path may contain wildcards: * matches any run of characters and ? matches exactly one.
+#[cfg(feature = "glob")]
+fn expand_glob(pattern: &str) -> Result<Vec<String>, CliError> {
+    let path = std::path::Path::new(pattern);
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    let name_pattern: Vec<char> = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| CliError::usage(format!("invalid glob pattern {:?}", pattern)))?
+        .chars()
+        .collect();
+
+    let read_error = |e: std::io::Error| CliError::io(format!("error reading {}: {}", dir.display(), e));
+    let entries = std::fs::read_dir(dir).map_err(read_error)?;
+    let mut matches = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(read_error)?;
+        let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+        if entry.path().is_file() && wildcard_match(&name_pattern, &name) {
+            matches.push(entry.path().to_string_lossy().into_owned());
+        }
+    }
+
+    if matches.is_empty() {
+        return Err(CliError::io(format!("no files matched {:?}", pattern)));
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+// Without the glob feature there's nothing to expand with, so say how to get it rather than failing to parse "--glob" as a number.
+#[cfg(not(feature = "glob"))]
+fn expand_glob(_pattern: &str) -> Result<Vec<String>, CliError> {
+    Err(CliError::usage("--glob requires building with `--features glob`"))
+}
+
+// Slice patterns let us look at the first character of each side. A * either matches nothing, or swallows one character of the name and tries again.
+#[cfg(feature = "glob")]
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern, name) {
+        ([], []) => true,
+        (['*', rest @ ..], _) => {
+            wildcard_match(rest, name) || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (['?', rest @ ..], [_, name_rest @ ..]) => wildcard_match(rest, name_rest),
+        ([p, rest @ ..], [c, name_rest @ ..]) if p == c => wildcard_match(rest, name_rest),
+        _ => false,
+    }
+}
+
+// Helpers for testing run. #[cfg(test)] means they're only compiled for cargo test, just like the #[test] functions.
+// run_with_stdin feeds run a list of arguments and some stdin text, and hands back the exit code along with everything written to stdout and stderr.
+#[cfg(test)]
+fn run_with_stdin(args: &[&str], input: &str) -> (i32, String, String) {
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let result = run(&args, &mut input.as_bytes(), &mut stdout, &mut stderr);
+    let code = report(result, &mut stderr);
+    (code, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+}
+
+#[cfg(test)]
+fn run_args(args: &[&str]) -> (i32, String, String) {
+    run_with_stdin(args, "")
+}
+
+// Writes contents to a file in the system temp directory and returns its path. The process id keeps parallel test runs from stepping on each other's files.
+#[cfg(test)]
+fn fixture(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("gcd-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_multiple_files() {
+    let first = fixture("multiple-a.txt", "84 126\n");
+    let second = fixture("multiple-b.txt", "210\n294 42\n");
+
+    let (code, stdout, _) = run_args(&["--file", &first, "--file", &second]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [84, 126, 210, 294, 42] is 42\n");
+}
+
+#[test]
+fn test_missing_file() {
+    let (code, _, stderr) = run_args(&["--file", "/no/such/gcd-input.txt"]);
+    assert_eq!(code, 3);
+    assert!(stderr.contains("/no/such/gcd-input.txt"));
+}
+
+#[cfg(feature = "glob")]
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_glob() {
+    let dir = std::env::temp_dir().join(format!("gcd-{}-glob", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.txt"), "36 60\n").unwrap();
+    std::fs::write(dir.join("b.txt"), "84\n").unwrap();
+    std::fs::write(dir.join("skip.dat"), "7\n").unwrap();
+
+    let pattern = dir.join("*.txt");
+    let (code, stdout, _) = run_args(&["--glob", pattern.to_str().unwrap()]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [36, 60, 84] is 12\n");
+
+    let pattern = dir.join("*.csv");
+    let (code, _, stderr) = run_args(&["--glob", pattern.to_str().unwrap()]);
+    assert_eq!(code, 3);
+    assert!(stderr.contains("no files matched"));
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_csv() {
+    let path = fixture("numbers.csv", "1,48,\"a, b\"\n2, 18 ,c\n3,30,d\n");
+    let (code, stdout, _) = run_args(&["--csv", &path, "--column", "1", "--no-echo"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6\n");
+
+    let (code, _, stderr) = run_args(&["--column", "2", "--csv", &path]);
+    assert_eq!(code, 2);
+    assert_eq!(stderr, format!("{} row 1: error parsing argument \"a, b\": invalid digit 'a' for base 10\n", path));
+
+    let path = fixture("ragged.csv", "12,18\n24\n");
+    let (code, _, _) = run_args(&["--csv", &path, "--column", "size"]);
+    assert_eq!(code, 1);
+    let (code, _, stderr) = run_args(&["--csv", &path, "--column", "1"]);
+    assert_eq!(code, 2);
+    assert_eq!(stderr, format!("{} row 2 has no column 1\n", path));
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_csv_header() {
+    let path = fixture("named.csv", "id, size ,label\n1,48,a\n2,18,b\n3,30,c\n");
+    let (code, stdout, _) = run_args(&["--csv", &path, "--header", "--column", "size", "--no-echo"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6\n");
+
+    let (_, stdout, _) = run_args(&["--csv", &path, "--header", "--column", "0", "--no-echo"]);
+    assert_eq!(stdout, "1\n");
+
+    let (code, _, stderr) = run_args(&["--csv", &path, "--header", "--column", "weight"]);
+    assert_eq!(code, 2);
+    assert_eq!(stderr, format!("{} has no column named \"weight\"\n", path));
+
+    let (code, _, stderr) = run_args(&["--csv", &path, "--header", "--column", "label"]);
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with(&format!("{} row 2: ", path)));
+}
+
+// In tests stderr isn't a terminal, so no bar is drawn; what matters is that asking for one doesn't change the answer.
+#[cfg(feature = "progress")]
+#[test]
+fn test_progress() {
+    let (code, with_progress, stderr) = run_args(&["--progress", "--seed-numbers", "25000", "--seed", "3"]);
+    assert_eq!(code, 0);
+    assert_eq!(stderr, "");
+    let (_, without, _) = run_args(&["--seed-numbers", "25000", "--seed", "3"]);
+    assert_eq!(with_progress, without);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_stdin_radix() {
+    // 0x30 and 0x48 are 48 and 72, the arguments are plain decimal.
+    let (code, stdout, _) = run_with_stdin(&["--stdin", "--stdin-radix", "16", "24", "60"], "30 48\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [24, 60, 48, 72] is 12\n");
+
+    // Without --stdin-radix, stdin follows --input-base.
+    let (code, stdout, _) = run_with_stdin(&["--stdin", "--input-base", "16", "c"], "1e\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [12, 30] is 6\n");
+}
+
+#[test]
+fn test_bezout() {
+    let (code, stdout, _) = run_args(&["bezout", "240", "46"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "gcd(240, 46) = 2 = 240*(-9) + 46*47\n");
+
+    let (code, _, stderr) = run_args(&["bezout", "240", "46", "12"]);
+    assert_eq!(code, 1);
+    assert!(stderr.starts_with("Usage: gcd bezout A B"));
+}
+
+#[test]
+fn test_explain() {
+    let (code, stdout, _) = run_args(&["--explain", "48", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "Reduced 48 and 18: 48 = 2*18 + 12, remainder 12.\n\
+         Reduced 18 and 12: 18 = 1*12 + 6, remainder 6.\n\
+         Reduced 12 and 6: 12 = 2*6 + 0, remainder 0.\n\
+         The remainder is 0, so the greatest common divisor of 48 and 18 is 6.\n"
+    );
+
+    let (_, stdout, _) = run_args(&["--explain", "12", "18", "8"]);
+    assert!(stdout.starts_with("Folding gcd over [12, 18, 8] one number at a time.\ngcd(12, 18):\n  Reduced 18 and 12"));
+    assert!(stdout.contains("gcd(6, 8):\n"));
+    assert!(stdout.ends_with("The greatest common divisor of [12, 18, 8] is 2.\n"));
+}
+
+#[test]
+fn test_assert_coprime() {
+    let (code, stdout, _) = run_args(&["--assert-coprime", "9", "28", "25"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "[9, 28, 25] are coprime\n");
+
+    let (code, stdout, stderr) = run_args(&["--assert-coprime", "12", "18"]);
+    assert_eq!(code, 5);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "[12, 18] are not coprime: they share the common factor 6\n");
+
+    // The check comes before the other modes, so they can't skip it.
+    for mode in ["--histogram", "--coprime-count", "--explain", "--tree-output"] {
+        let (code, stdout, _) = run_args(&["--assert-coprime", mode, "12", "18"]);
+        assert_eq!(code, 5, "wrong exit code with {}", mode);
+        assert_eq!(stdout, "");
+    }
+    for json in ["--json", "--ndjson"] {
+        let (code, _, stderr) = run_args(&["--assert-coprime", json, "9", "28"]);
+        assert_eq!(code, 1);
+        assert!(stderr.starts_with("--assert-coprime answers in words"));
+    }
+
+    let (code, stdout, _) = run_args(&["--assert-coprime", "--coprime-count", "4", "9"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "1 of 1 pairs are coprime (100.0%)\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_from_ranges() {
+    // A..B leaves out B, like a Rust range.
+    let (code, stdout, _) = run_args(&["--from-ranges", "6..12"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [6, 7, 8, 9, 10, 11] is 1\n");
+
+    let (_, stdout, _) = run_args(&["--from-ranges", "6..8", "20"]);
+    assert_eq!(stdout, "The greatest common divisor of [6, 7, 20] is 1\n");
+
+    let (_, stdout, _) = run_args(&["--from-ranges", "12..=12", "18"]);
+    assert_eq!(stdout, "The greatest common divisor of [12, 18] is 6\n");
+
+    let (code, _, stderr) = run_args(&["--from-ranges", "12..6"]);
+    assert_eq!(code, 2);
+    assert_eq!(stderr, "range \"12..6\" is empty\n");
+
+    let (code, _, _) = run_args(&["--from-ranges", "6..6"]);
+    assert_eq!(code, 2);
+}
+
+#[test]
+fn test_from_ranges_zero() {
+    let (code, stdout, stderr) = run_args(&["--from-ranges", "0..4"]);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "range \"0..4\" contains 0, and gcd isn't defined for 0\n");
+
+    let (code, _, _) = run_args(&["--from-ranges", "0..=0"]);
+    assert_eq!(code, 2);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_dash_reads_stdin_in_place() {
+    let (code, stdout, _) = run_with_stdin(&["30", "-", "45"], "60 75\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [30, 60, 75, 45] is 15\n");
+
+    let (code, _, stderr) = run_with_stdin(&["-", "30", "-"], "60\n");
+    assert_eq!(code, 1);
+    assert!(stderr.starts_with("stdin can only be read once"));
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_max_iterations() {
+    let (code, stdout, _) = run_args(&["--max-iterations", "100", "48", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [48, 18] is 6\n");
+
+    let (code, _, stderr) = run_args(&["--max-iterations", "1", "48", "18"]);
+    assert_eq!(code, 5);
+    assert_eq!(stderr, "gcd(48, 18) did not finish within 1 iterations\n");
+}
+
+#[test]
+fn test_lines_separators() {
+    let input = "12 18\n\n20 30 50\n7\n";
+
+    let (code, stdout, _) = run_with_stdin(&["--lines"], input);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6\n10\n7\n");
+
+    let (_, stdout, _) = run_with_stdin(&["--lines", "--space"], input);
+    assert_eq!(stdout, "6 10 7\n");
+}
+
+#[test]
+fn test_lines_keep_going() {
+    let input = "12 18\n4 banana\n0 5\n20 30 50\n";
+
+    // Without --keep-going the bad line stops everything.
+    let (code, stdout, _) = run_with_stdin(&["--lines"], input);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "");
+
+    let (code, stdout, stderr) = run_with_stdin(&["--lines", "--keep-going"], input);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "6\nerror\nerror\n10\n");
+    assert_eq!(
+        stderr,
+        "line 2: error parsing argument \"banana\": invalid digit 'b' for base 10\nline 3: gcd isn't defined for 0\n2 of the lines of stdin couldn't be computed\n"
+    );
+
+    let (_, stdout, _) = run_with_stdin(&["--lines", "--keep-going", "--ndjson"], input);
+    assert_eq!(
+        stdout,
+        concat!(
+            "{\"inputs\":[12,18],\"gcd\":6}\n",
+            "{\"line\":2,\"error\":\"error parsing argument \\\"banana\\\": invalid digit 'b' for base 10\"}\n",
+            "{\"line\":3,\"error\":\"gcd isn't defined for 0\"}\n",
+            "{\"inputs\":[20,30,50],\"gcd\":10}\n"
+        )
+    );
+
+    let (code, _, _) = run_args(&["--keep-going", "12", "18"]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn test_no_input_error() {
+    let (code, stdout, stderr) = run_args(&[]);
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    assert!(stderr.starts_with("Usage: gcd"));
+
+    let (code, stdout, stderr) = run_args(&["--no-input-error"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "0\n");
+    assert_eq!(stderr, "");
+
+    let (code, stdout, _) = run_args(&["--no-input-error", "--json"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "{\"numbers\":[],\"gcd\":0}\n");
+    assert_eq!(run_args(&["--no-input-error", "--ndjson"]).1, "{\"inputs\":[],\"gcd\":0}\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_prefixed_and_underscored_inputs() {
+    let numbers = fixture("prefixed.txt", "0x3c 1_20\n");
+    let (code, stdout, _) = run_with_stdin(&["0b11000", "--file", &numbers, "-"], "0o110\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [24, 60, 120, 72] is 12\n");
+}
+
+#[test]
+fn test_gcd_modes_reject_zero() {
+    for args in [
+        &["0", "6"][..],
+        &["--explain", "0", "6"],
+        &["--verbose", "12", "0"],
+        &["--tree-output", "0", "6"],
+        &["--trace-json", "0", "6"],
+        &["--explain-json", "0", "6"],
+        &["--factor-common", "0", "6"],
+        &["--histogram", "0", "6"],
+    ] {
+        let (code, stdout, stderr) = run_args(args);
+        assert_eq!(code, 2, "wrong exit code for {:?}", args);
+        assert_eq!(stdout, "");
+        assert!(stderr.ends_with("is 0, and gcd isn't defined for 0\n"), "{:?} gave {:?}", args, stderr);
+    }
+    let (_, _, stderr) = run_args(&["--verbose", "12", "0"]);
+    assert_eq!(stderr, "number 2 of the input is 0, and gcd isn't defined for 0\n");
+
+    // A lone 0 is its own gcd, but it still can't be factored.
+    assert_eq!(run_args(&["--no-echo", "0"]).1, "0\n");
+    assert_eq!(run_args(&["--factor-common", "0"]).0, 1);
+}
+
+#[test]
+fn test_histogram() {
+    // 12 = 2^2 * 3, 18 = 2 * 3^2, 20 = 2^2 * 5, 35 = 5 * 7
+    let (code, stdout, _) = run_args(&["--histogram", "12", "18", "20", "35"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "2: 3 of 4\n3: 2 of 4\n5: 2 of 4\n7: 1 of 4\n");
+
+    // Repeats are factored once but still counted each time.
+    assert_eq!(run_args(&["--histogram", "12", "12", "35", "12"]).1, "2: 3 of 4\n3: 3 of 4\n5: 1 of 4\n7: 1 of 4\n");
+}
+
+// Snapshot tests compare output byte for byte, so it has to be the same on every run, including the order of primes that tie.
+#[test]
+fn test_histogram_is_deterministic() {
+    let args = ["--histogram", "30", "42", "66", "70", "105", "154", "390"];
+    let (_, first, _) = run_args(&args);
+    for _ in 0..10 {
+        assert_eq!(run_args(&args).1, first);
+    }
+    assert_eq!(first, "2: 6 of 7\n3: 5 of 7\n5: 4 of 7\n7: 4 of 7\n11: 2 of 7\n13: 1 of 7\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_parallel_threshold_flag() {
+    // Both sides of the threshold have to agree on the answer.
+    let (_, sequential, _) = run_args(&["--parallel-threshold", "4", "24", "36", "60", "84"]);
+    let (_, parallel, _) = run_args(&["--parallel-threshold", "3", "24", "36", "60", "84"]);
+    assert_eq!(sequential, "The greatest common divisor of [24, 36, 60, 84] is 12\n");
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_bare_list() {
+    let (_, stdout, _) = run_args(&["42", "56"]);
+    assert_eq!(stdout, "The greatest common divisor of [42, 56] is 14\n");
+
+    let (_, stdout, _) = run_args(&["--bare-list", "42", "56"]);
+    assert_eq!(stdout, "The greatest common divisor of 42 56 is 14\n");
+}
+
+#[test]
+fn test_nthprime() {
+    let (code, stdout, _) = run_args(&["nthprime", "6"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "13\n");
+
+    let (code, _, stderr) = run_args(&["nthprime", "0"]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("no 0th prime"));
+}
+
+#[test]
+fn test_divisors_subcommand() {
+    let (code, stdout, _) = run_args(&["divisors", "12", "--space"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "1 2 3 4 6 12\n");
+
+    let (_, stdout, _) = run_args(&["divisors", "7"]);
+    assert_eq!(stdout, "1\n7\n");
+}
+
+#[test]
+fn test_pairwise_coprime_flag() {
+    let (code, stdout, _) = run_args(&["--pairwise-coprime", "3", "4", "5"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "[3, 4, 5] are pairwise coprime\n");
+
+    let (code, _, _) = run_args(&["--pairwise-coprime", "--json", "3", "4", "5"]);
+    assert_eq!(code, 1);
+
+    let (code, _, stderr) = run_args(&["--pairwise-coprime", "6", "10", "15"]);
+    assert_eq!(code, 5);
+    assert_eq!(stderr, "[6, 10, 15] are not pairwise coprime\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_dedup_flag() {
+    let (_, plain, _) = run_args(&["--parallel-threshold", "2", "30", "45", "30", "45", "75"]);
+    let (_, dedup, _) = run_args(&["--parallel-threshold", "2", "--dedup", "30", "45", "30", "45", "75"]);
+    assert_eq!(plain, "The greatest common divisor of [30, 45, 30, 45, 75] is 15\n");
+    assert_eq!(dedup, plain);
+}
+
+#[test]
+fn test_tree_output() {
+    let (code, stdout, _) = run_args(&["--tree-output", "12", "18", "8", "20"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "2\n\
+         |-- 6\n\
+         |   |-- 12\n\
+         |   `-- 18\n\
+         `-- 4\n    \
+         |-- 8\n    \
+         `-- 20\n"
+    );
+}
+
+#[test]
+fn test_as_fraction() {
+    let (code, stdout, _) = run_args(&["--as-fraction", "0.375", "0.1", "2.50", "7", ".5"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "0.375 = 3/8\n0.1 = 1/10\n2.50 = 5/2\n7 = 7/1\n.5 = 1/2\n");
+
+    for bad in &["1.2.3", ".", "-0.5", "1e3"] {
+        let (code, _, _) = run_args(&["--as-fraction", bad]);
+        assert_eq!(code, 2, "{} should be rejected", bad);
+    }
+    let (code, _, _) = run_args(&["--as-fraction", "0.12345678901234567890"]);
+    assert_eq!(code, 4);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_seed_numbers() {
+    let (code, first, _) = run_args(&["--seed-numbers", "5", "--seed", "7"]);
+    assert_eq!(code, 0);
+    let (_, second, _) = run_args(&["--seed", "7", "--seed-numbers", "5"]);
+    assert_eq!(first, second);
+    assert_eq!(first, format!("The greatest common divisor of {:?} is 1\n", seeded_numbers(5, 7)));
+
+    let (_, other, _) = run_args(&["--seed-numbers", "5", "--seed", "8"]);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn test_exit_codes() {
+    let failures: &[(&[&str], ExitCode)] = &[
+        (&[], ExitCode::Usage),
+        (&["--seed", "seven"], ExitCode::Usage),
+        (&["12", "x"], ExitCode::Parse),
+        (&["--file", "/no/such/gcd-input.txt"], ExitCode::Io),
+        (&["99999999999999999999", "6"], ExitCode::Overflow),
+        (&["--assert-coprime", "12", "18"], ExitCode::CheckFailed),
+    ];
+    for (args, expected) in failures {
+        let (code, _, stderr) = run_args(args);
+        assert_eq!(code, *expected as i32, "wrong exit code for {:?}", args);
+        assert!(!stderr.is_empty());
+    }
+
+    let args: Vec<String> = ["12", "x"].iter().map(|s| s.to_string()).collect();
+    let error = run(&args, &mut "".as_bytes(), &mut Vec::new(), &mut Vec::new()).unwrap_err();
+    assert_eq!(error.code, ExitCode::Parse);
+}
+
+#[test]
+fn test_exit_code() {
+    for (args, status) in [(&["12", "18"][..], 6), (&["7", "21", "35"], 7), (&["255", "510"], 255), (&["14", "15"], 1)] {
+        let args: Vec<&str> = std::iter::once("--exit-code").chain(args.iter().copied()).collect();
+        let (code, stdout, stderr) = run_args(&args);
+        assert_eq!(code, status, "wrong status for {:?}", args);
+        assert_eq!(stdout, "");
+        assert_eq!(stderr, "");
+    }
+
+    let (code, _, stderr) = run_args(&["--exit-code", "256", "512"]);
+    assert_eq!(code, ExitCode::Overflow as i32);
+    assert_eq!(stderr, "the gcd is 256, but --exit-code can only give a gcd of at most 255\n");
+
+    let (code, _, stderr) = run_args(&["--exit-code", "0", "5"]);
+    assert_eq!(code, ExitCode::Parse as i32);
+    assert_eq!(stderr, "number 1 of the input is 0, and gcd isn't defined for 0\n");
+    assert_eq!(run_args(&["--exit-code", "0"]).0, ExitCode::Parse as i32);
+
+    let (code, _, _) = run_args(&["--exit-code", "--lines"]);
+    assert_eq!(code, ExitCode::Usage as i32);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_tee() {
+    let path = std::env::temp_dir().join(format!("gcd-{}-tee.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+    let (code, stdout, _) = run_args(&["--tee", path, "--bare-list", "12", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of 12 18 is 6\n");
+    assert_eq!(std::fs::read_to_string(path).unwrap(), stdout);
+    std::fs::remove_file(path).unwrap();
+
+    // A file that can't be written is an error, but the result still reaches stdout.
+    let (code, stdout, stderr) = run_args(&["--tee", "/no/such/dir/gcd-tee.txt", "12", "18"]);
+    assert_eq!(code, 3);
+    assert_eq!(stdout, "The greatest common divisor of [12, 18] is 6\n");
+    assert!(stderr.starts_with("error writing /no/such/dir/gcd-tee.txt"));
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_algorithm_flag() {
+    let (code, stdout, _) = run_args(&["--algorithm", "Binary", "48", "18", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [48, 18, 30] is 6\n");
+
+    let (code, _, stderr) = run_args(&["--algorithm", "stein", "48", "18"]);
+    assert_eq!(code, 1);
+    assert_eq!(stderr, "unknown gcd algorithm \"stein\", expected euclid or binary\n");
+}
+
+#[test]
+fn test_convert() {
+    let (code, stdout, _) = run_args(&["convert", "ff", "--from", "16", "--to", "2"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "11111111\n");
+
+    let (_, stdout, _) = run_args(&["convert", "255", "--to", "36"]);
+    assert_eq!(stdout, "73\n");
+
+    let (code, _, stderr) = run_args(&["convert", "12", "--from", "2", "--to", "10"]);
+    assert_eq!(code, 2);
+    assert_eq!(stderr, "error parsing argument \"12\": invalid digit '2' for base 2\n");
+
+    let (code, _, _) = run_args(&["convert", "--from", "16", "--to", "2"]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_verify() {
+    for seed in 0..20 {
+        let seed = seed.to_string();
+        let (code, checked, _) = run_args(&["--verify", "--seed-numbers", "50", "--seed", &seed]);
+        assert_eq!(code, 0);
+        let (_, plain, _) = run_args(&["--seed-numbers", "50", "--seed", &seed]);
+        assert_eq!(checked, plain);
+    }
+    let (code, stdout, _) = run_args(&["--verify", "--algorithm", "binary", "1071", "462", "147"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [1071, 462, 147] is 21\n");
+}
+
+#[test]
+fn test_verbose_summary() {
+    let (code, stdout, _) = run_args(&["--verbose", "12", "18", "8"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout.matches("Reduced").count(), 4);
+    assert!(stdout.ends_with("The greatest common divisor of [12, 18, 8] is 2.\nProcessed 3 numbers in 4 reduction steps; GCD = 2\n"));
+
+    let (_, stdout, _) = run_args(&["--verbose", "7"]);
+    assert!(stdout.ends_with("Processed 1 number in 0 reduction steps; GCD = 7\n"));
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_strict() {
+    let (code, stdout, _) = run_args(&["--strict", "42", "18446744073709551615"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [42, 18446744073709551615] is 3\n");
+    let (code, _, _) = run_args(&["--strict", "0"]);
+    assert_eq!(code, 0);
+
+    for (token, message) in &[
+        ("007", "leading zeros aren't allowed"),
+        ("+42", "a leading + isn't allowed"),
+        ("0x2a", "leading zeros aren't allowed"),
+        ("1_000", "invalid digit '_' for base 10"),
+        ("", "no digits"),
+    ] {
+        let (code, _, stderr) = run_args(&["--strict", "12", token]);
+        assert_eq!(code, 2);
+        assert_eq!(stderr, format!("error parsing argument {:?}: {}\n", token, message));
+    }
+    // The same inputs are fine without --strict.
+    let (code, _, _) = run_args(&["007", "+42", "0x2a", "1_000"]);
+    assert_eq!(code, 0);
+
+    let (code, _, _) = run_args(&["--strict", "--input-base", "16", "ff"]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn test_no_echo() {
+    let (code, stdout, _) = run_args(&["--no-echo", "48", "18", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_grouping() {
+    let (code, stdout, _) = run_args(&["--grouping", "comma", "1,234,567", "1234567000"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [1234567, 1234567000] is 1234567\n");
+
+    let (_, stdout, _) = run_args(&["--grouping", "space", "1 000 000", "1000"]);
+    assert_eq!(stdout, "The greatest common divisor of [1000000, 1000] is 1000\n");
+
+    let (code, _, stderr) = run_args(&["--grouping", "comma", "12,34"]);
+    assert_eq!(code, 2);
+    assert_eq!(stderr, "error parsing argument \"12,34\": grouping separators have to come every three digits\n");
+
+    // Without --grouping a comma is just an invalid digit.
+    let (code, _, _) = run_args(&["1,234"]);
+    assert_eq!(code, 2);
+}
+
+#[test]
+fn test_repl() {
+    let (code, stdout, _) = run_with_stdin(&["repl"], "12 18\n\n0 4\n12 x\n18 12 12\nquit\n30 45\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "> 6\n> > gcd isn't defined for 0\n> error parsing argument \"x\": invalid digit 'x' for base 10\n> 6\n> \n");
+}
+
+#[test]
+fn test_repl_cache() {
+    let mut cache = HashMap::new();
+    assert_eq!(cached_gcd(&mut cache, &[12, 18]), (6, false));
+    assert_eq!(cached_gcd(&mut cache, &[18, 12, 12]), (6, true));
+    assert_eq!(cached_gcd(&mut cache, &[12, 18, 8]), (2, false));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_lcm_subcommand() {
+    let (code, stdout, _) = run_args(&["lcm", "4", "6", "10"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The least common multiple of [4, 6, 10] is 60\n");
+
+    let (code, _, stderr) = run_args(&["lcm", "8589934592", "4294967295", "3"]);
+    assert_eq!(code, 4);
+    assert_eq!(stderr, "the least common multiple of [8589934592, 4294967295, 3] is too large for a u64\n");
+}
+
+#[test]
+fn test_explain_overflow() {
+    let (code, _, stderr) = run_args(&["lcm", "--explain-overflow", "18446744073709551615", "18446744073709551614"]);
+    assert_eq!(code, 4);
+    assert!(stderr.contains("= 18446744073709551615 * 18446744073709551614 = 340282366920938463408034375210639556610, which needs 128 bits"));
+
+    let (_, _, stderr) = run_args(&["lcm", "--explain-overflow", "12", "8589934592", "4294967295"]);
+    assert!(stderr.ends_with("lcm(25769803776, 4294967295) = (25769803776 / 3) * 4294967295 = 8589934592 * 4294967295 = 36893488138829168640, which needs 65 bits, but a u64 only has 64\n"));
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_min_gcd() {
+    let (code, stdout, _) = run_args(&["--min-gcd", "6", "48", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [48, 18] is 6\n");
+
+    let (code, stdout, stderr) = run_args(&["--min-gcd", "7", "48", "18"]);
+    assert_eq!(code, 5);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "the greatest common divisor of [48, 18] is 6, less than 7\n");
+}
+
+#[test]
+fn test_min_gcd_gates_every_mode() {
+    for args in [&["--explain", "--min-gcd", "100", "12", "18"][..], &["--min-gcd", "100", "--pairwise-coprime", "3", "4", "5"], &["--min-gcd", "7", "--histogram", "12", "18"]] {
+        let (code, stdout, _) = run_args(args);
+        assert_eq!(code, 5, "wrong exit code for {:?}", args);
+        assert_eq!(stdout, "");
+    }
+
+    // Once the gate passes, the mode prints what it always does.
+    let (code, stdout, _) = run_args(&["--min-gcd", "6", "--factor-common", "12", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "2 * 3 = 6\n");
+}
+
+#[test]
+fn test_factor_common() {
+    let (code, stdout, _) = run_args(&["--factor-common", "360", "84"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "2^2 * 3 = 12\n");
+
+    let (_, stdout, _) = run_args(&["--factor-common", "720", "1080", "600"]);
+    assert_eq!(stdout, "2^3 * 3 * 5 = 120\n");
+
+    let (_, stdout, _) = run_args(&["--factor-common", "14", "15"]);
+    assert_eq!(stdout, "1 = 1\n");
+}
+
+#[test]
+fn test_content_subcommand() {
+    let (code, stdout, _) = run_args(&["content", "6", "-9", "12"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The content of [6, -9, 12] is 3\n");
+
+    let (_, stdout, _) = run_args(&["content", "-9223372036854775808", "0"]);
+    assert_eq!(stdout, "The content of [-9223372036854775808, 0] is 9223372036854775808\n");
+
+    let (code, _, _) = run_args(&["content", "9223372036854775808"]);
+    assert_eq!(code, 4);
+    let (code, _, _) = run_args(&["content", "-x"]);
+    assert_eq!(code, 2);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_null_separated() {
+    let (code, stdout, _) = run_with_stdin(&["-0", "--stdin"], "48\x0018\x0030\x00");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [48, 18, 30] is 6\n");
+
+    let path = fixture("nul.txt", "1,000\x002,500\n");
+    let (_, stdout, _) = run_args(&["--null-separated", "--grouping", "comma", "--file", &path, "--no-echo"]);
+    assert_eq!(stdout, "500\n");
+
+    // Without the flag, NUL bytes aren't whitespace, so they end up inside the tokens.
+    let (code, _, _) = run_with_stdin(&["--stdin"], "48\x0018\n");
+    assert_eq!(code, 2);
+}
+
+#[test]
+fn test_batch_file() {
+    let batch = fixture("batch.txt", "12 18\n\n7 21 35\n4 banana\n0 5\n100\n");
+    let (code, stdout, stderr) = run_args(&["--batch-file", &batch]);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "6\n\n7\nline 4: error parsing argument \"banana\": invalid digit 'b' for base 10\nline 5: gcd isn't defined for 0\n100\n");
+    assert!(stderr.contains("2 of the lines"));
+
+    // --keep-going changes nothing here, since a batch always carries on past a bad line.
+    let (code, kept, _) = run_args(&["--batch-file", &batch, "--keep-going"]);
+    assert_eq!(code, 2);
+    assert_eq!(kept, stdout);
+
+    // --output sends the same lines to a file, and nothing to stdout.
+    let good = fixture("batch-good.txt", "12 18\n9 6\n");
+    let output = std::env::temp_dir().join(format!("gcd-{}-batch-out.txt", std::process::id()));
+    let output = output.to_str().unwrap();
+    let (code, stdout, _) = run_args(&["--batch-file", &good, "--output", output]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "");
+    assert_eq!(std::fs::read_to_string(output).unwrap(), "6\n3\n");
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_max_value() {
+    let (code, stdout, stderr) = run_args(&["--max-value", "1000", "12", "18446744073709551615", "18", "5000"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [12, 18] is 6\n");
+    assert_eq!(stderr, "dropped 2 of 4 numbers above --max-value 1000\n");
+
+    // Nothing to drop, nothing said.
+    let (_, _, stderr) = run_args(&["--max-value", "1000", "12", "18"]);
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn test_ndjson() {
+    let (code, stdout, _) = run_with_stdin(&["--lines", "--ndjson"], "12 18\n\n7 21 35\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "{\"inputs\":[12,18],\"gcd\":6}\n{\"inputs\":[7,21,35],\"gcd\":7}\n");
+
+    let batch = fixture("batch-ndjson.txt", "12 18\n\n4 x\n100\n");
+    let (code, stdout, _) = run_args(&["--batch-file", &batch, "--ndjson"]);
+    assert_eq!(code, 2);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "{\"inputs\":[12,18],\"gcd\":6}");
+    assert_eq!(lines[1], "{\"line\":3,\"error\":\"error parsing argument \\\"x\\\": invalid digit 'x' for base 10\"}");
+    assert_eq!(lines[2], "{\"inputs\":[100],\"gcd\":100}");
+    assert_eq!(lines.len(), 3);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_input_sep() {
+    let (code, stdout, _) = run_args(&["--input-sep", "[|:]", "42|56:98"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [42, 56, 98] is 14\n");
+
+    let (code, stdout, _) = run_with_stdin(&["--input-sep", "[|:]", "--stdin"], "42||56\n98:\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [42, 56, 98] is 14\n");
+
+    let (code, _, stderr) = run_args(&["--input-sep", "(|)", "42"]);
+    assert_eq!(code, 1);
+    assert!(stderr.starts_with("--input-sep pattern"));
+}
+
+// The exact bytes, key order included, so anything snapshot-testing --json output can rely on them.
+#[test]
+fn test_json_output() {
+    let (code, stdout, _) = run_args(&["--json", "48", "18", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout.as_bytes(), b"{\"numbers\":[48,18,30],\"gcd\":6}\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_fail_on_zero() {
+    let (code, stdout, stderr) = run_args(&["--fail-on-zero", "12", "0", "18"]);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "number 2 of the input is 0, which --fail-on-zero doesn't allow\n");
+
+    let (code, _, _) = run_with_stdin(&["--lines", "--fail-on-zero"], "12 18\n0 5\n");
+    assert_eq!(code, 2);
+
+    let (code, stdout, _) = run_args(&["--fail-on-zero", "12", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [12, 18] is 6\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_require_count() {
+    let (code, stdout, _) = run_args(&["--require-count", "3", "12", "18", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [12, 18, 30] is 6\n");
+
+    let (code, stdout, stderr) = run_args(&["--require-count", "3", "12", "18"]);
+    assert_eq!(code, 5);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "expected 3 numbers but read 2\n");
+
+    let (code, _, stderr) = run_with_stdin(&["--require-count", "2", "-"], "12 18 18\n");
+    assert_eq!(code, 5);
+    assert_eq!(stderr, "expected 2 numbers but read 3\n");
+}
+
+#[cfg(feature = "minimal")]
+#[test]
+fn test_minimal_output() {
+    let (code, stdout, _) = run_args(&["48", "18", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6\n");
+    let (code, _, stderr) = run_args(&[]);
+    assert_eq!(code, 1);
+    assert_eq!(stderr, "Usage: gcd [OPTION]... NUMBER ...\n");
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_compare_groups() {
+    let (code, stdout, _) = run_args(&["12", "18", "--", "6", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [12, 18] is 6\nThe greatest common divisor of [6, 30] is 6\n");
+
+    let (code, stdout, _) = run_args(&["--no-echo", "--space", "12", "18", "--", "6", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6 6\n");
+    let (_, stdout, _) = run_args(&["--ndjson", "--space", "12", "18", "--", "6", "30"]);
+    assert_eq!(stdout, "{\"inputs\":[12,18],\"gcd\":6}\n{\"inputs\":[6,30],\"gcd\":6}\n");
+
+    let (code, _, stderr) = run_args(&["--no-echo", "12", "18", "--", "6", "35", "--", "24", "30"]);
+    assert_eq!(code, 5);
+    assert_eq!(stderr, "the groups have different greatest common divisors: [6, 1, 6]\n");
+
+    let (code, _, stderr) = run_args(&["12", "18", "--"]);
+    assert_eq!(code, 1);
+    assert_eq!(stderr, "group 2 has no numbers in it\n");
+}
+
+#[test]
+fn test_trace_json() {
+    let (code, stdout, _) = run_args(&["--trace-json", "48", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        concat!(
+            "{\"numbers\":[48,18],\"steps\":[",
+            "{\"a\":48,\"b\":18,\"quotient\":2,\"remainder\":12},",
+            "{\"a\":18,\"b\":12,\"quotient\":1,\"remainder\":6},",
+            "{\"a\":12,\"b\":6,\"quotient\":2,\"remainder\":0}",
+            "],\"gcd\":6}\n"
+        )
+    );
+
+    let (_, stdout, _) = run_args(&["--trace-json", "7"]);
+    assert_eq!(stdout, "{\"numbers\":[7],\"steps\":[],\"gcd\":7}\n");
+}
+
+#[test]
+fn test_explain_json() {
+    let (code, stdout, _) = run_args(&["--explain-json", "48", "18"]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        concat!(
+            "{\"inputs\":[48,18],\"steps\":[",
+            "{\"a\":48,\"b\":18,\"operation\":\"48 mod 18\",\"result\":12},",
+            "{\"a\":18,\"b\":12,\"operation\":\"18 mod 12\",\"result\":6},",
+            "{\"a\":12,\"b\":6,\"operation\":\"12 mod 6\",\"result\":0}",
+            "],\"gcd\":6}\n"
+        )
+    );
+}
+
+#[test]
+fn test_coprime_count() {
+    // Of the six pairs, only the three with 7 in them are coprime: 6, 10 and 15 each share a factor with the other two.
+    let (code, stdout, _) = run_args(&["--coprime-count", "6", "10", "15", "7"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "3 of 6 pairs are coprime (50.0%)\n");
+
+    let (_, stdout, _) = run_args(&["--coprime-count", "4", "9", "25"]);
+    assert_eq!(stdout, "3 of 3 pairs are coprime (100.0%)\n");
+
+    let (code, _, stderr) = run_args(&["--coprime-count", "--seed-numbers", "10001"]);
+    assert_eq!(code, 1);
+    assert!(stderr.starts_with("--coprime-count compares every pair"));
+}
+
+// The broken pipe itself ends the process, so it can't be tried from inside a test (see PipeStdout for how to see it by hand). What can be checked is that nothing else is swallowed.
+#[test]
+fn test_exit_on_broken_pipe_passes_other_results() {
+    assert_eq!(exit_on_broken_pipe(Ok(3)).unwrap(), 3);
+    let error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "no");
+    assert_eq!(exit_on_broken_pipe::<()>(Err(error)).unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+}
+
+// Stands in for a stderr whose reader has gone away.
+#[cfg(test)]
+struct ClosedPipe;
+
+#[cfg(test)]
+impl Write for ClosedPipe {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+#[test]
+fn test_report_to_closed_stderr() {
+    assert_eq!(report(Err(CliError::parse("bad number")), &mut ClosedPipe), ExitCode::Parse as i32);
+    assert_eq!(ignore_broken_pipe(ClosedPipe.write(b"dropped"), 7).unwrap(), 7);
+    let error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "no");
+    assert!(ignore_broken_pipe(Err(error), 0).is_err());
+}
+
+#[test]
+fn test_dry_run() {
+    let path = fixture("dry-run.txt", "12 18\n30\n");
+    let (code, stdout, _) = run_args(&["--dry-run", "--file", &path, "42"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "Read 4 numbers with no errors; nothing was computed\n");
+
+    let (code, stdout, stderr) = run_args(&["--dry-run", "12", "x", "--file", &path, "y"]);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "Read 4 numbers, but 2 of the inputs had errors; nothing was computed\n");
+    assert!(!stdout.contains("greatest common divisor"));
+    assert_eq!(stderr.lines().count(), 2);
+    assert!(stderr.contains("\"x\"") && stderr.contains("\"y\""));
+}
+
+#[test]
+fn test_dry_run_rejects_computing_modes() {
+    let batch = fixture("dry-run-batch.txt", "12 18\n");
+    let (code, stdout, stderr) = run_args(&["--dry-run", "--batch-file", &batch]);
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    assert!(stderr.starts_with("--dry-run can't be combined with"));
+
+    let (code, stdout, _) = run_with_stdin(&["--dry-run", "--lines"], "12 18\n");
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    assert_eq!(run_args(&["reduce", "--dry-run", "6/8"]).0, 1);
+}
+
+#[test]
+fn test_reduce_subcommand() {
+    let (code, stdout, _) = run_args(&["reduce", "6/8", "10/4", "0/5"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6/8 = 3/4\n10/4 = 5/2\n0/5 = 0/1\n");
+
+    // Without --validate-fraction the fractions before the bad one are still printed.
+    let (code, stdout, stderr) = run_args(&["reduce", "6/8", "1/0", "x/2"]);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "6/8 = 3/4\n");
+    assert_eq!(stderr, "\"1/0\" has a zero denominator\n");
+}
+
+#[test]
+fn test_validate_fraction() {
+    let (code, stdout, stderr) = run_args(&["reduce", "--validate-fraction", "6/8", "1/0", "x/2", "7", "10/4"]);
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "");
+    let problems: Vec<&str> = stderr.lines().collect();
+    assert_eq!(problems.len(), 3);
+    assert_eq!(problems[0], "fraction 2: \"1/0\" has a zero denominator");
+    assert!(problems[1].starts_with("fraction 3: error parsing argument \"x\""));
+    assert_eq!(problems[2], "fraction 4: \"7\" is not a fraction like 6/8");
+
+    let (code, stdout, _) = run_args(&["reduce", "--validate-fraction", "6/8", "10/4"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6/8 = 3/4\n10/4 = 5/2\n");
+}
+
+#[test]
+fn test_limit() {
+    let (code, stdout, _) = run_args(&["--no-echo", "--limit", "3", "12", "18", "30"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "6\n");
+
+    let (code, _, stderr) = run_args(&["--limit", "2", "12", "18", "30", "42"]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("got 4 numbers as arguments"));
+    assert!(stderr.contains("--limit allows at most 2"));
+    assert!(stderr.contains("--file PATH"));
+
+    // Numbers read from stdin aren't arguments, so they don't count.
+    let (code, _, _) = run_with_stdin(&["--limit", "1", "12", "-"], "18 30 42\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_merge_stdin() {
+    let path = fixture("merge.txt", "84 126\n");
+    let (code, stdout, _) = run_with_stdin(&["--merge-stdin", "--file", &path], "210\n42\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [84, 126, 210, 42] is 42\n");
+
+    let (code, _, stderr) = run_with_stdin(&["--merge-stdin", "12"], "18\n");
+    assert_eq!(code, 1);
+    assert!(stderr.contains("needs at least one --file"));
+}
+
+#[test]
+fn test_compare_subcommand() {
+    let (code, stdout, _) = run_args(&["compare", "12", "18", "--", "8", "12"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, ">\n");
+    let (_, stdout, _) = run_args(&["compare", "8", "12", "--", "12", "18"]);
+    assert_eq!(stdout, "<\n");
+    let (_, stdout, _) = run_args(&["compare", "12", "18", "--", "6"]);
+    assert_eq!(stdout, "=\n");
+
+    let (code, _, _) = run_args(&["compare", "12", "18"]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+#[cfg_attr(feature = "minimal", ignore = "checks the full sentence")]
+fn test_exec() {
+    let (code, stdout, _) = run_args(&["--exec", "echo 42 56", "98"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "The greatest common divisor of [42, 56, 98] is 14\n");
+
+    let (code, _, stderr) = run_args(&["--exec", "false"]);
+    assert_eq!(code, 3);
+    assert!(stderr.starts_with("\"false\" failed with exit status: 1"));
 
-    assert_eq!(gcd(2*3*5*11*17, 3*7*11*13*19), 3*11);
+    let (code, _, stderr) = run_args(&["--exec", "/no/such/gcd-program"]);
+    assert_eq!(code, 3);
+    assert!(stderr.starts_with("error running"));
 }
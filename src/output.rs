@@ -0,0 +1,164 @@
+// Formatting Results
+
+use hello::GcdStep;
+
+// Everything there is to say about one gcd calculation, handed to whichever formatter the flags picked.
+pub struct GcdOutcome<'a> {
+    pub numbers: &'a [u64],
+    pub gcd: u64,
+}
+
+// A trait lists methods a type must provide. Each way of printing the result is its own type implementing OutputFormatter,
+// so the code that computes the gcd can hold a Box<dyn OutputFormatter> and call format without knowing or caring which one it has.
+// Adding an output mode means writing one more impl and choosing it in parse_options.
+pub trait OutputFormatter {
+    fn format(&self, outcome: &GcdOutcome) -> String;
+}
+
+// The default sentence: The greatest common divisor of [48, 18] is 6. With --bare-list the list is written 48 18.
+// The minimal feature leaves it out, along with its text.
+// With no numbers at all, which --no-input-error allows, there's no list to echo, so it's just the gcd, 0.
+#[cfg(not(feature = "minimal"))]
+pub struct Prose {
+    pub bare_list: bool,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl OutputFormatter for Prose {
+    fn format(&self, outcome: &GcdOutcome) -> String {
+        if outcome.numbers.is_empty() {
+            return outcome.gcd.to_string();
+        }
+        format!("The greatest common divisor of {} is {}", list_text(outcome.numbers, self.bare_list), outcome.gcd)
+    }
+}
+
+// Just the gcd, for --no-echo.
+pub struct NumberOnly;
+
+impl OutputFormatter for NumberOnly {
+    fn format(&self, outcome: &GcdOutcome) -> String {
+        outcome.gcd.to_string()
+    }
+}
+
+// One JSON object per result, {"inputs":[48,18],"gcd":6}, for --ndjson. Written one to a line, that's NDJSON (newline-delimited JSON), which tools like jq read a line at a time.
+// The fields are only numbers, which JSON writes just as Rust does, so the object is put together by hand in a fixed order.
+pub struct Ndjson;
+
+impl OutputFormatter for Ndjson {
+    fn format(&self, outcome: &GcdOutcome) -> String {
+        format!("{{\"inputs\":{},\"gcd\":{}}}", json_array(outcome.numbers), outcome.gcd)
+    }
+}
+
+// The result as a JSON object for --json: {"numbers":[48,18],"gcd":6}.
+// The keys are always written in this order, numbers then gcd, the same order as GcdOutcome's fields, so the same input always gives byte-for-byte the same output
+// and the output can be compared against a saved copy. A HashMap would give no such promise, since the order it iterates in can change from run to run.
+pub struct Json;
+
+impl OutputFormatter for Json {
+    fn format(&self, outcome: &GcdOutcome) -> String {
+        format!("{{\"numbers\":{},\"gcd\":{}}}", json_array(outcome.numbers), outcome.gcd)
+    }
+}
+
+// Every division the Euclidean algorithm made, for --trace-json:
+// {"numbers":[48,18],"steps":[{"a":48,"b":18,"quotient":2,"remainder":12},...],"gcd":6}. Each step has GcdStep's fields, in the order they're declared.
+// With more than two numbers the steps of each gcd in the fold follow on from one another, and a step whose a and b don't follow from the one before is where the next pair starts.
+pub fn trace_json(numbers: &[u64], steps: &[GcdStep], gcd: u64) -> String {
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            format!("{{\"a\":{},\"b\":{},\"quotient\":{},\"remainder\":{}}}", step.a, step.b, step.quotient, step.remainder)
+        })
+        .collect();
+    format!("{{\"numbers\":{},\"steps\":[{}],\"gcd\":{}}}", json_array(numbers), steps.join(","), gcd)
+}
+
+// The --explain walkthrough as JSON for --explain-json, for a frontend to lay out however it likes. The object always has these fields, in this order:
+//   inputs     the numbers, as a JSON array
+//   steps      one object per division, in the order they were made, each with
+//                a          the number being divided
+//                b          what it's divided by
+//                operation  the division as text, like "48 mod 18"
+//                result     what's left over, which is the b of the next step; the last step's result is 0
+//   gcd        the answer
+// With one number there's nothing to divide, so steps is empty. Unlike --trace-json's steps, these don't say how many times b went into a, only what was left over.
+pub fn explain_json(numbers: &[u64], steps: &[GcdStep], gcd: u64) -> String {
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            let operation = json_string(&format!("{} mod {}", step.a, step.b));
+            format!("{{\"a\":{},\"b\":{},\"operation\":{},\"result\":{}}}", step.a, step.b, operation, step.remainder)
+        })
+        .collect();
+    format!("{{\"inputs\":{},\"steps\":[{}],\"gcd\":{}}}", json_array(numbers), steps.join(","), gcd)
+}
+
+// A list of numbers as a JSON array, with no spaces: [48,18].
+fn json_array(numbers: &[u64]) -> String {
+    let items: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+    format!("[{}]", items.join(","))
+}
+
+// Writes text as a JSON string, quotes included. A quote or backslash gets a backslash in front, and control characters like newlines are written as \u escapes, since JSON strings can't hold them as they are.
+pub fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                json.push('\\');
+                json.push(c);
+            }
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// How a list of numbers is echoed back: [42, 56] by default, which is what {:?} gives a slice, or 42 56 when bare for anything that would rather not strip brackets.
+pub fn list_text(numbers: &[u64], bare: bool) -> String {
+    if bare {
+        let text: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+        text.join(" ")
+    } else {
+        format!("{:?}", numbers)
+    }
+}
+
+#[cfg(not(feature = "minimal"))]
+#[test]
+fn test_prose_formatter() {
+    let outcome = GcdOutcome { numbers: &[48, 18], gcd: 6 };
+    assert_eq!(Prose { bare_list: false }.format(&outcome), "The greatest common divisor of [48, 18] is 6");
+    assert_eq!(Prose { bare_list: true }.format(&outcome), "The greatest common divisor of 48 18 is 6");
+    assert_eq!(Prose { bare_list: false }.format(&GcdOutcome { numbers: &[], gcd: 0 }), "0");
+}
+
+#[test]
+fn test_number_only_formatter() {
+    let outcome = GcdOutcome { numbers: &[48, 18], gcd: 6 };
+    assert_eq!(NumberOnly.format(&outcome), "6");
+}
+
+#[test]
+fn test_ndjson_formatter() {
+    let outcome = GcdOutcome { numbers: &[48, 18], gcd: 6 };
+    assert_eq!(Ndjson.format(&outcome), "{\"inputs\":[48,18],\"gcd\":6}");
+}
+
+#[test]
+fn test_json_formatter() {
+    let outcome = GcdOutcome { numbers: &[48, 18], gcd: 6 };
+    assert_eq!(Json.format(&outcome), "{\"numbers\":[48,18],\"gcd\":6}");
+    assert_eq!(Json.format(&GcdOutcome { numbers: &[7], gcd: 7 }), "{\"numbers\":[7],\"gcd\":7}");
+}
+
+#[test]
+fn test_json_string() {
+    assert_eq!(json_string("plain"), "\"plain\"");
+    assert_eq!(json_string("a \"b\" \\ c\n"), "\"a \\\"b\\\" \\\\ c\\u000a\"");
+}
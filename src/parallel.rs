@@ -0,0 +1,91 @@
+// Parallel Reduction
+
+use std::collections::HashSet;
+
+use crate::{gcd, gcd_all};
+
+// gcd is associative, gcd(gcd(a, b), c) == gcd(a, gcd(b, c)), so a long slice can be cut into chunks, each reduced on its own thread, and the chunk results reduced at the end.
+// std::thread::scope lets the threads borrow values directly, because it waits for all of them to finish before returning.
+pub fn gcd_all_parallel(values: &[u64]) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    // Round up so there are never more chunks than threads.
+    let chunk_len = values.len().div_ceil(threads);
+    let partials: Vec<u64> = std::thread::scope(|scope| {
+        let handles: Vec<_> = values
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || gcd_all(chunk).unwrap()))
+            .collect();
+        // join waits for a thread and hands back its result, or the panic if it had one, which unwrap passes on.
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    partials.into_iter().reduce(gcd)
+}
+
+// Starting threads costs far more than a few thousand gcd calls, so below this many numbers the plain sequential fold is faster.
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 100_000;
+
+// Reduces slices with gcd, switching to gcd_all_parallel only for inputs longer than parallel_threshold.
+// With dedup set, those long inputs have their duplicates dropped first. A repeated number can't change the gcd, so for inputs full of repeats
+// that's work the threads never have to do. Below the threshold it's skipped, since building the set costs about as much as the gcds it would save.
+#[derive(Debug, Clone, Copy)]
+pub struct GcdCalculator {
+    pub parallel_threshold: usize,
+    pub dedup: bool,
+}
+
+impl Default for GcdCalculator {
+    fn default() -> GcdCalculator {
+        GcdCalculator { parallel_threshold: DEFAULT_PARALLEL_THRESHOLD, dedup: false }
+    }
+}
+
+impl GcdCalculator {
+    pub fn uses_parallel(&self, len: usize) -> bool {
+        len > self.parallel_threshold
+    }
+
+    pub fn gcd_all(&self, values: &[u64]) -> Option<u64> {
+        if self.uses_parallel(values.len()) && self.dedup {
+            // Collecting into a HashSet keeps one copy of each value, in no particular order, which is all gcd needs.
+            let unique: HashSet<u64> = values.iter().copied().collect();
+            let unique: Vec<u64> = unique.into_iter().collect();
+            gcd_all_parallel(&unique)
+        } else if self.uses_parallel(values.len()) {
+            gcd_all_parallel(values)
+        } else {
+            gcd_all(values)
+        }
+    }
+}
+
+#[test]
+fn test_gcd_all_parallel() {
+    assert_eq!(gcd_all_parallel(&[]), None);
+    assert_eq!(gcd_all_parallel(&[42]), Some(42));
+    let values: Vec<u64> = (1..=1000).map(|n| n * 6).collect();
+    assert_eq!(gcd_all_parallel(&values), Some(6));
+}
+
+#[test]
+fn test_parallel_threshold() {
+    let calculator = GcdCalculator { parallel_threshold: 3, dedup: false };
+    assert!(!calculator.uses_parallel(3));
+    assert!(calculator.uses_parallel(4));
+    assert_eq!(calculator.gcd_all(&[12, 18, 30]), Some(6));
+    assert_eq!(calculator.gcd_all(&[12, 18, 30, 42]), Some(6));
+
+    assert!(!GcdCalculator::default().uses_parallel(DEFAULT_PARALLEL_THRESHOLD));
+}
+
+#[test]
+fn test_dedup() {
+    let values: Vec<u64> = (0..10_000).map(|n| [84, 126, 210][n % 3]).collect();
+    let plain = GcdCalculator { parallel_threshold: 100, dedup: false };
+    let dedup = GcdCalculator { parallel_threshold: 100, dedup: true };
+    assert_eq!(plain.gcd_all(&values), Some(42));
+    assert_eq!(dedup.gcd_all(&values), Some(42));
+    assert_eq!(dedup.gcd_all(&values[..50]), Some(42));
+}
@@ -0,0 +1,209 @@
+// Parsing Numbers
+
+// Every place that reads numbers (arguments, stdin, files) goes through parse_u64_token, so they all accept the same spellings:
+// plain digits like 1000, a base prefix like 0xff, 0o17 or 0b101, and underscores between digits like 1_000_000, the same as in Rust source code.
+use std::fmt;
+
+// What went wrong parsing a token. Each variant carries enough to say exactly what was wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTokenError {
+    Empty,
+    InvalidDigit { digit: char, radix: u32 },
+    MisplacedUnderscore,
+    Overflow,
+    LeadingZero,
+    PlusSign,
+    MisplacedSeparator,
+}
+
+// Display is what {} uses, so implementing it is how the error gets a human-readable message.
+impl fmt::Display for ParseTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseTokenError::Empty => write!(f, "no digits"),
+            ParseTokenError::InvalidDigit { digit, radix } => write!(f, "invalid digit {:?} for base {}", digit, radix),
+            ParseTokenError::MisplacedUnderscore => write!(f, "underscores can only go between digits"),
+            ParseTokenError::Overflow => write!(f, "number is too large for a u64"),
+            ParseTokenError::LeadingZero => write!(f, "leading zeros aren't allowed"),
+            ParseTokenError::PlusSign => write!(f, "a leading + isn't allowed"),
+            ParseTokenError::MisplacedSeparator => write!(f, "grouping separators have to come every three digits"),
+        }
+    }
+}
+
+impl std::error::Error for ParseTokenError {}
+
+// Parses a decimal number, or one in another base if it starts with 0x, 0o or 0b.
+pub fn parse_u64_token(token: &str) -> Result<u64, ParseTokenError> {
+    parse_u64_token_radix(token, 10)
+}
+
+// The same, reading unprefixed digits in base radix. A prefix still wins when radix is 10, or when it names radix itself (0x with 16).
+// Otherwise the prefix is just digits: in base 16, 0b1 is the number 0xb1.
+pub fn parse_u64_token_radix(token: &str, radix: u32) -> Result<u64, ParseTokenError> {
+    // A leading + is allowed, like u64::from_str does.
+    let token = token.strip_prefix('+').unwrap_or(token);
+    let (digits, radix) = match split_prefix(token) {
+        Some((prefix_radix, rest)) if radix == 10 || radix == prefix_radix => (rest, prefix_radix),
+        _ => (token, radix),
+    };
+    if digits.is_empty() {
+        return Err(ParseTokenError::Empty);
+    }
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return Err(ParseTokenError::MisplacedUnderscore);
+    }
+
+    let mut value: u64 = 0;
+    for c in digits.chars().filter(|c| *c != '_') {
+        let digit = c.to_digit(radix).ok_or(ParseTokenError::InvalidDigit { digit: c, radix })?;
+        // checked_mul and checked_add return None instead of wrapping around, which is exactly when the number is too big.
+        value = value
+            .checked_mul(radix as u64)
+            .and_then(|v| v.checked_add(digit as u64))
+            .ok_or(ParseTokenError::Overflow)?;
+    }
+    Ok(value)
+}
+
+// A stricter parser for validating data: a number has to be written the one canonical way, plain decimal digits with no + in front and no leading zeros (except 0 itself).
+// So 007, +42, 0x2a and 1_000 are all refused, though parse_u64_token would take them. Each rule broken gets its own error.
+pub fn parse_u64_token_strict(token: &str) -> Result<u64, ParseTokenError> {
+    if token.starts_with('+') {
+        return Err(ParseTokenError::PlusSign);
+    }
+    if token.len() > 1 && token.starts_with('0') {
+        return Err(ParseTokenError::LeadingZero);
+    }
+    if let Some(c) = token.chars().find(|c| !c.is_ascii_digit()) {
+        return Err(ParseTokenError::InvalidDigit { digit: c, radix: 10 });
+    }
+    parse_u64_token_radix(token, 10)
+}
+
+// Takes the thousands separators out of a number like 1,234,567 so it can be parsed. They have to be in the right places:
+// one to three digits before the first separator and exactly three between each one after that, so 12,34 and 1,,234 are refused. A leading + doesn't count as a digit.
+// A token without any separators comes back as it was.
+pub fn strip_grouping(token: &str, separator: char) -> Result<String, ParseTokenError> {
+    if !token.contains(separator) {
+        return Ok(token.to_string());
+    }
+    let digits = token.strip_prefix('+').unwrap_or(token);
+    let mut groups = digits.split(separator);
+    let first = groups.next().unwrap().chars().count();
+    if first == 0 || first > 3 || groups.any(|group| group.chars().count() != 3) {
+        return Err(ParseTokenError::MisplacedSeparator);
+    }
+    Ok(token.replace(separator, ""))
+}
+
+// Splits off a 0x, 0o or 0b prefix (either case), returning the base it stands for and the digits after it.
+fn split_prefix(token: &str) -> Option<(u32, &str)> {
+    let prefix = token.get(..2)?;
+    let radix = match prefix {
+        "0x" | "0X" => 16,
+        "0o" | "0O" => 8,
+        "0b" | "0B" => 2,
+        _ => return None,
+    };
+    Some((radix, &token[2..]))
+}
+
+// The other direction: writes value in base radix (2 to 36), using lowercase letters for digits past 9.
+// Digits come out least significant first, so they're collected and then reversed.
+pub fn format_u64_radix(mut value: u64, radix: u32) -> String {
+    assert!((2..=36).contains(&radix));
+    if value == 0 {
+        return "0".to_string();
+    }
+    let mut digits = Vec::new();
+    while value > 0 {
+        digits.push(std::char::from_digit((value % radix as u64) as u32, radix).unwrap());
+        value /= radix as u64;
+    }
+    digits.iter().rev().collect()
+}
+
+#[test]
+fn test_parse_decimal() {
+    assert_eq!(parse_u64_token("42"), Ok(42));
+    assert_eq!(parse_u64_token("+42"), Ok(42));
+    assert_eq!(parse_u64_token("0"), Ok(0));
+    assert_eq!(parse_u64_token("18446744073709551615"), Ok(u64::MAX));
+}
+
+#[test]
+fn test_parse_prefixes() {
+    assert_eq!(parse_u64_token("0xff"), Ok(255));
+    assert_eq!(parse_u64_token("0XFF"), Ok(255));
+    assert_eq!(parse_u64_token("0o17"), Ok(15));
+    assert_eq!(parse_u64_token("0b101"), Ok(5));
+}
+
+#[test]
+fn test_parse_underscores() {
+    assert_eq!(parse_u64_token("1_000_000"), Ok(1_000_000));
+    assert_eq!(parse_u64_token("0xff_ff"), Ok(0xffff));
+    assert_eq!(parse_u64_token("_1"), Err(ParseTokenError::MisplacedUnderscore));
+    assert_eq!(parse_u64_token("1_"), Err(ParseTokenError::MisplacedUnderscore));
+    assert_eq!(parse_u64_token("1__0"), Err(ParseTokenError::MisplacedUnderscore));
+    assert_eq!(parse_u64_token("0x_ff"), Err(ParseTokenError::MisplacedUnderscore));
+}
+
+#[test]
+fn test_parse_radix() {
+    assert_eq!(parse_u64_token_radix("ff", 16), Ok(255));
+    assert_eq!(parse_u64_token_radix("0xff", 16), Ok(255));
+    // 0b is a prefix for base 2, not 16, so here it's the hex digits 0, b, 1.
+    assert_eq!(parse_u64_token_radix("0b1", 16), Ok(0xb1));
+    assert_eq!(parse_u64_token_radix("0b1", 8), Err(ParseTokenError::InvalidDigit { digit: 'b', radix: 8 }));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_u64_token(""), Err(ParseTokenError::Empty));
+    assert_eq!(parse_u64_token("0x"), Err(ParseTokenError::Empty));
+    assert_eq!(parse_u64_token("-5"), Err(ParseTokenError::InvalidDigit { digit: '-', radix: 10 }));
+    assert_eq!(parse_u64_token("12a"), Err(ParseTokenError::InvalidDigit { digit: 'a', radix: 10 }));
+    assert_eq!(parse_u64_token("0b102"), Err(ParseTokenError::InvalidDigit { digit: '2', radix: 2 }));
+    assert_eq!(parse_u64_token("18446744073709551616"), Err(ParseTokenError::Overflow));
+    assert_eq!(parse_u64_token("12a").unwrap_err().to_string(), "invalid digit 'a' for base 10");
+}
+
+#[test]
+fn test_format_radix() {
+    assert_eq!(format_u64_radix(0, 2), "0");
+    assert_eq!(format_u64_radix(255, 2), "11111111");
+    assert_eq!(format_u64_radix(255, 16), "ff");
+    assert_eq!(format_u64_radix(35, 36), "z");
+    assert_eq!(format_u64_radix(u64::MAX, 16), "ffffffffffffffff");
+    for radix in 2..=36 {
+        assert_eq!(parse_u64_token_radix(&format_u64_radix(123_456_789, radix), radix), Ok(123_456_789));
+    }
+}
+
+#[test]
+fn test_parse_strict() {
+    assert_eq!(parse_u64_token_strict("0"), Ok(0));
+    assert_eq!(parse_u64_token_strict("42"), Ok(42));
+    assert_eq!(parse_u64_token_strict("18446744073709551615"), Ok(u64::MAX));
+    assert_eq!(parse_u64_token_strict("18446744073709551616"), Err(ParseTokenError::Overflow));
+    assert_eq!(parse_u64_token_strict("007"), Err(ParseTokenError::LeadingZero));
+    assert_eq!(parse_u64_token_strict("00"), Err(ParseTokenError::LeadingZero));
+    assert_eq!(parse_u64_token_strict("+42"), Err(ParseTokenError::PlusSign));
+    assert_eq!(parse_u64_token_strict("0x2a"), Err(ParseTokenError::LeadingZero));
+    assert_eq!(parse_u64_token_strict("1_000"), Err(ParseTokenError::InvalidDigit { digit: '_', radix: 10 }));
+    assert_eq!(parse_u64_token_strict(""), Err(ParseTokenError::Empty));
+}
+
+#[test]
+fn test_strip_grouping() {
+    assert_eq!(strip_grouping("1,234,567", ','), Ok("1234567".to_string()));
+    assert_eq!(strip_grouping("123,456", ','), Ok("123456".to_string()));
+    assert_eq!(strip_grouping("+1,000", ','), Ok("+1000".to_string()));
+    assert_eq!(strip_grouping("1 000", ' '), Ok("1000".to_string()));
+    assert_eq!(strip_grouping("1234567", ','), Ok("1234567".to_string()));
+    for bad in &["12,34", "1,,234", ",123", "123,", "1234,567", "1,2345"] {
+        assert_eq!(strip_grouping(bad, ','), Err(ParseTokenError::MisplacedSeparator), "{}", bad);
+    }
+}
@@ -0,0 +1,230 @@
+// Primes and Factoring
+
+use std::collections::HashMap;
+
+use crate::gcd_or_zero;
+
+// Breaks n into its prime factors, returned as (prime, exponent) pairs from the smallest prime up: 360 = 2^3 * 3^2 * 5 gives [(2, 3), (3, 2), (5, 1)].
+// This is trial division, which is quick for anything with small factors but slow when n has two large ones. 1 has no prime factors, and 0 can't be factored at all.
+pub fn prime_factors(mut n: u64) -> Vec<(u64, u32)> {
+    assert!(n != 0, "0 has no prime factorization");
+    let mut factors = Vec::new();
+    let mut p = 2;
+    // Only primes up to sqrt(n) need trying: whatever is left after dividing those out is 1 or a prime itself. Writing p <= n / p rather than p * p <= n can't overflow.
+    while p <= n / p {
+        let mut exponent = 0;
+        while n.is_multiple_of(p) {
+            n /= p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+        // After 2, only odd numbers can be prime.
+        p += if p == 2 { 1 } else { 2 };
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+// Remembers the factorization of every number it's asked about, like GcdCache does for gcds. Factoring is the slow part of --histogram,
+// and with a cache each distinct number is only factored once however many times it turns up in the input. 0 panics, as it does in prime_factors.
+#[derive(Debug, Default)]
+pub struct FactorCache {
+    map: HashMap<u64, Vec<(u64, u32)>>,
+}
+
+impl FactorCache {
+    pub fn new() -> FactorCache {
+        FactorCache::default()
+    }
+
+    // Hands back a slice borrowed from the cache rather than a fresh Vec, so a repeat lookup doesn't copy anything.
+    pub fn factors(&mut self, n: u64) -> &[(u64, u32)] {
+        self.map.entry(n).or_insert_with(|| prime_factors(n))
+    }
+}
+
+// base^exp mod modulus by repeated squaring, so it takes about log2(exp) multiplications instead of exp of them.
+// Each product is worked out in u128, since two numbers below a u64 modulus can multiply to almost 128 bits.
+pub fn mod_exp(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    assert!(modulus != 0, "modulus must be nonzero");
+    let m = modulus as u128;
+    let mut result = 1 % m;
+    let mut base = base as u128 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    result as u64
+}
+
+// The Legendre symbol (a/p): 1 if a is a nonzero square mod p, -1 if it isn't, and 0 if p divides a.
+// By Euler's criterion a^((p-1)/2) mod p is always 0, 1, or p - 1, and p - 1 stands for -1. That only holds when p is an odd prime;
+// checking primality is up to the caller, but an even p is refused outright.
+pub fn legendre(a: i64, p: u64) -> i32 {
+    assert!(p > 2 && p % 2 == 1, "the Legendre symbol needs an odd prime, got {}", p);
+    // rem_euclid gives a remainder from 0 to p - 1 even for negative a, where % would give a negative one. i128 fits both a and p.
+    let a = (a as i128).rem_euclid(p as i128) as u64;
+    match mod_exp(a, (p - 1) / 2, p) {
+        0 => 0,
+        1 => 1,
+        _ => -1,
+    }
+}
+
+// Miller-Rabin primality test. For each base a it checks a property every prime has; a composite fails it for most bases.
+// "Probable" is the name it usually goes by, but testing these twelve bases (the primes up to 37) is known to catch every composite below 2^64, so for a u64 the answer is exact.
+pub fn is_probable_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    // Small primes and their multiples are settled by division alone.
+    for p in BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    // Write n - 1 as d * 2^s with d odd.
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'bases: for a in BASES {
+        let mut x = mod_exp(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = ((x as u128 * x as u128) % n as u128) as u64;
+            if x == n - 1 {
+                // A label on a loop lets continue skip to the next base from inside the inner loop.
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// The nth prime counting from 1, so nth_prime(1) is 2 and nth_prime(6) is 13. It tests each number in turn, which is fine for making test data but slow for very large n.
+// There is no 0th prime, so n == 0 panics.
+pub fn nth_prime(n: u64) -> u64 {
+    assert!(n != 0, "primes are counted from 1");
+    let mut count = 0;
+    let mut candidate = 1;
+    while count < n {
+        candidate += 1;
+        if is_probable_prime(candidate) {
+            count += 1;
+        }
+    }
+    candidate
+}
+
+// Every divisor of n, smallest first. Divisors come in pairs d and n / d with one of them at most sqrt(n), so we only have to search that far,
+// collecting the small half in order and the large half in reverse order. divisors(1) is [1], and divisors(0) is empty, since every number divides 0 and there's no list to give.
+pub fn divisors(n: u64) -> Vec<u64> {
+    let mut small = Vec::new();
+    let mut large = Vec::new();
+    let mut d = 1;
+    while n != 0 && d <= n / d {
+        if n.is_multiple_of(d) {
+            small.push(d);
+            if d != n / d {
+                large.push(n / d);
+            }
+        }
+        d += 1;
+    }
+    // large was filled from the biggest partner down, so reversed it carries on in order where small leaves off.
+    large.reverse();
+    small.extend(large);
+    small
+}
+
+// Every number that divides both a and b, smallest first: common_divisors(12, 18) gives 1, 2, 3, 6.
+// Those are exactly the divisors of gcd(a, b), so that's all this is. A 0 is divided by everything, so common_divisors(12, 0) is just the divisors of 12.
+// Returning impl Iterator lets callers stop early or collect into whatever they like without knowing it's a Vec underneath.
+pub fn common_divisors(a: u64, b: u64) -> impl Iterator<Item = u64> {
+    divisors(gcd_or_zero(a, b)).into_iter()
+}
+
+#[test]
+fn test_divisors() {
+    assert_eq!(divisors(12), vec![1, 2, 3, 4, 6, 12]);
+    assert_eq!(divisors(36), vec![1, 2, 3, 4, 6, 9, 12, 18, 36]);
+    assert_eq!(divisors(13), vec![1, 13]);
+    assert_eq!(divisors(1), vec![1]);
+    assert_eq!(divisors(0), vec![]);
+}
+
+#[test]
+fn test_common_divisors() {
+    assert_eq!(common_divisors(12, 18).collect::<Vec<_>>(), [1, 2, 3, 6]);
+    assert_eq!(common_divisors(18, 12).collect::<Vec<_>>(), [1, 2, 3, 6]);
+    assert_eq!(common_divisors(14, 15).collect::<Vec<_>>(), [1]);
+    assert_eq!(common_divisors(12, 0).collect::<Vec<_>>(), [1, 2, 3, 4, 6, 12]);
+    assert_eq!(common_divisors(0, 0).count(), 0);
+}
+
+#[test]
+fn test_is_probable_prime() {
+    let small: Vec<u64> = (0..50).filter(|n| is_probable_prime(*n)).collect();
+    assert_eq!(small, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47]);
+    // The largest prime below 2^64, and a Carmichael number that fools simpler tests.
+    assert!(is_probable_prime(18446744073709551557));
+    assert!(!is_probable_prime(561));
+    assert!(!is_probable_prime(u64::MAX));
+}
+
+#[test]
+fn test_nth_prime() {
+    assert_eq!(nth_prime(1), 2);
+    assert_eq!(nth_prime(6), 13);
+    assert_eq!(nth_prime(1000), 7919);
+}
+
+#[test]
+fn test_mod_exp() {
+    assert_eq!(mod_exp(2, 10, 1000), 24);
+    assert_eq!(mod_exp(3, 0, 7), 1);
+    assert_eq!(mod_exp(5, 3, 1), 0);
+    assert_eq!(mod_exp(u64::MAX - 1, 2, u64::MAX), 1);
+}
+
+#[test]
+fn test_legendre() {
+    assert_eq!(legendre(2, 7), 1);
+    assert_eq!(legendre(3, 7), -1);
+    assert_eq!(legendre(14, 7), 0);
+    assert_eq!(legendre(-1, 7), -1);
+    assert_eq!(legendre(-1, 13), 1);
+}
+
+#[test]
+fn test_prime_factors() {
+    assert_eq!(prime_factors(1), vec![]);
+    assert_eq!(prime_factors(2), vec![(2, 1)]);
+    assert_eq!(prime_factors(360), vec![(2, 3), (3, 2), (5, 1)]);
+    assert_eq!(prime_factors(97), vec![(97, 1)]);
+    assert_eq!(prime_factors(u64::MAX), vec![(3, 1), (5, 1), (17, 1), (257, 1), (641, 1), (65537, 1), (6700417, 1)]);
+}
+
+#[test]
+fn test_factor_cache() {
+    let mut cache = FactorCache::new();
+    for n in [1, 2, 360, 97, 360, 1024, 97, u64::MAX] {
+        assert_eq!(cache.factors(n), &prime_factors(n)[..]);
+    }
+    assert_eq!(cache.map.len(), 6);
+
+    // Planting a wrong answer shows the next lookup really comes from the cache.
+    cache.map.insert(12, vec![(5, 1)]);
+    assert_eq!(cache.factors(12), &[(5, 1)]);
+}
@@ -0,0 +1,244 @@
+// Separator Patterns
+
+// A small regular expression, just big enough to describe what separates numbers: --input-sep '[|:]' or --input-sep ', *|;'.
+// It understands single characters, . for any character, classes like [|:], [0-9] and [^a-z], the escapes \d \s \w (and \D \S \W for their opposites),
+// the repeats * + and ?, and | between whole alternatives. Grouping with ( ), counted repeats like {2} and the anchors ^ and $ aren't there; a backslash turns any of them into a plain character.
+#[derive(Debug)]
+pub struct Pattern {
+    alternatives: Vec<Vec<Piece>>,
+}
+
+// One atom and how many times in a row it may match. max is None for no limit.
+#[derive(Debug)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    max: Option<usize>,
+}
+
+// Something that matches exactly one character.
+#[derive(Debug)]
+enum Atom {
+    Char(char),
+    Any,
+    Class { negated: bool, items: Vec<ClassItem> },
+}
+
+#[derive(Debug)]
+enum ClassItem {
+    Range(char, char),
+    // One of the backslash shorthands, d s or w, or D S or W for everything they don't match.
+    Shorthand(char),
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Atom::Char(want) => c == *want,
+            Atom::Any => c != '\n',
+            Atom::Class { negated, items } => items.iter().any(|item| item.matches(c)) != *negated,
+        }
+    }
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ClassItem::Range(low, high) => (*low..=*high).contains(&c),
+            ClassItem::Shorthand(name) => {
+                let found = match name.to_ascii_lowercase() {
+                    'd' => c.is_ascii_digit(),
+                    's' => c.is_whitespace(),
+                    _ => c.is_alphanumeric() || c == '_',
+                };
+                found != name.is_ascii_uppercase()
+            }
+        }
+    }
+}
+
+impl Pattern {
+    // Reads a pattern, saying what's wrong with it if it can't be used.
+    pub fn new(pattern: &str) -> Result<Pattern, String> {
+        let mut alternatives: Vec<Vec<Piece>> = vec![Vec::new()];
+        // Working on a Vec of chars lets the class reader look two characters ahead for ranges like a-z.
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut i = 0;
+        while let Some(&c) = chars.get(i) {
+            i += 1;
+            // last_mut always finds something, since alternatives starts with one entry and | only ever adds more.
+            let pieces = alternatives.last_mut().unwrap();
+            let atom = match c {
+                '|' => {
+                    alternatives.push(Vec::new());
+                    continue;
+                }
+                '*' | '+' | '?' => {
+                    let piece = match pieces.last_mut() {
+                        Some(piece) if piece.min == 1 && piece.max == Some(1) => piece,
+                        Some(_) => return Err(format!("{:?} can't repeat something that's already repeated", c)),
+                        None => return Err(format!("{:?} has nothing before it to repeat", c)),
+                    };
+                    piece.min = if c == '+' { 1 } else { 0 };
+                    piece.max = if c == '?' { Some(1) } else { None };
+                    continue;
+                }
+                '(' | ')' | '{' | '}' | '^' | '$' => {
+                    return Err(format!("{:?} isn't supported here; write \\{} for the character itself", c, c))
+                }
+                '.' => Atom::Any,
+                '\\' => {
+                    i += 1;
+                    escape(*chars.get(i - 1).ok_or("the pattern ends with a lone \\")?)
+                }
+                '[' => class(&chars, &mut i)?,
+                c => Atom::Char(c),
+            };
+            pieces.push(Piece { atom, min: 1, max: Some(1) });
+        }
+        if alternatives.iter().any(|pieces| pieces.is_empty()) {
+            return Err("the pattern, or one of its | alternatives, is empty".to_string());
+        }
+        Ok(Pattern { alternatives })
+    }
+
+    // Cuts text up at every place the pattern matches, like str::split does for a fixed separator.
+    // A match has to use up at least one character, so a pattern like ,* only splits where there's actually a comma.
+    pub fn split<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        let mut pieces = Vec::new();
+        let mut piece_start = 0;
+        let mut search = 0;
+        while let Some((start, end)) = self.find(text, search) {
+            pieces.push(&text[piece_start..start]);
+            piece_start = end;
+            search = end;
+        }
+        pieces.push(&text[piece_start..]);
+        pieces
+    }
+
+    // The first match starting at or after byte from, as the byte range it covers. The alternatives are tried in order, as they are in other regex engines.
+    fn find(&self, text: &str, from: usize) -> Option<(usize, usize)> {
+        for (start, _) in text[from..].char_indices() {
+            let start = from + start;
+            for pieces in &self.alternatives {
+                match match_here(pieces, text, start) {
+                    Some(end) if end > start => return Some((start, end)),
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+// Tries to match pieces at byte pos of text, returning where the match ends.
+// Each piece first takes as many characters as it can, then gives them back one at a time until the rest of the pattern matches too: [0-9]*0 against 100 takes all three digits, then backs off one for the final 0.
+fn match_here(pieces: &[Piece], text: &str, pos: usize) -> Option<usize> {
+    let (piece, rest) = match pieces.split_first() {
+        Some(split) => split,
+        None => return Some(pos),
+    };
+    // ends[n] is where the text is after the piece has matched n times.
+    let mut ends = vec![pos];
+    let mut at = pos;
+    while piece.max.is_none_or(|max| ends.len() <= max) {
+        match text[at..].chars().next() {
+            Some(c) if piece.atom.matches(c) => {
+                at += c.len_utf8();
+                ends.push(at);
+            }
+            _ => break,
+        }
+    }
+    ends.iter().skip(piece.min).rev().find_map(|end| match_here(rest, text, *end))
+}
+
+// What a character after a backslash stands for.
+fn escape(c: char) -> Atom {
+    match c {
+        'd' | 'D' | 's' | 'S' | 'w' | 'W' => Atom::Class { negated: false, items: vec![ClassItem::Shorthand(c)] },
+        't' => Atom::Char('\t'),
+        'n' => Atom::Char('\n'),
+        c => Atom::Char(c),
+    }
+}
+
+// Reads a class like [|:] or [^0-9]. i starts just after the [ and is left just after the closing ].
+// A ] straight after the [ (or [^) is a plain character, so []|] matches ] or |.
+fn class(chars: &[char], i: &mut usize) -> Result<Atom, String> {
+    let mut next = || {
+        *i += 1;
+        chars.get(*i - 1).copied().ok_or_else(|| "a [ class is never closed with ]".to_string())
+    };
+    let mut c = next()?;
+    let negated = c == '^';
+    if negated {
+        c = next()?;
+    }
+    let mut items = Vec::new();
+    let mut first = true;
+    while c != ']' || first {
+        first = false;
+        let low = match c {
+            '\\' => match next()? {
+                name @ ('d' | 'D' | 's' | 'S' | 'w' | 'W') => {
+                    items.push(ClassItem::Shorthand(name));
+                    c = next()?;
+                    continue;
+                }
+                't' => '\t',
+                'n' => '\n',
+                c => c,
+            },
+            c => c,
+        };
+        c = next()?;
+        // A - between two characters makes a range. Last in the class it's just a -.
+        if c == '-' {
+            let high = next()?;
+            if high == ']' {
+                items.push(ClassItem::Range(low, low));
+                items.push(ClassItem::Range('-', '-'));
+                break;
+            }
+            if high < low {
+                return Err(format!("the range {}-{} goes backwards", low, high));
+            }
+            items.push(ClassItem::Range(low, high));
+            c = next()?;
+        } else {
+            items.push(ClassItem::Range(low, low));
+        }
+    }
+    Ok(Atom::Class { negated, items })
+}
+
+#[test]
+fn test_pattern_split() {
+    let split = |pattern: &str, text: &str| Pattern::new(pattern).unwrap().split(text).join(" ");
+    assert_eq!(split("[|:]", "42|56:98"), "42 56 98");
+    assert_eq!(split(", *|;", "1,2,  3;4"), "1 2 3 4");
+    assert_eq!(split("\\s*-+\\s*", "7 -- 8---9"), "7 8 9");
+    assert_eq!(split("[^0-9]", "1a2"), "1 2");
+    assert_eq!(split("x?,", "1x,2,3"), "1 2 3");
+    assert_eq!(split("a[0-9]*0", "1a1002"), "1 2");
+    assert_eq!(split("[]|]", "1]2|3"), "1 2 3");
+    // A pattern that can match nothing only splits where it matches something.
+    assert_eq!(split(",*", "1,,2"), "1 2");
+    assert_eq!(split("[a-]", "1-2a3"), "1 2 3");
+}
+
+#[test]
+fn test_pattern_errors() {
+    assert!(Pattern::new("").is_err());
+    assert!(Pattern::new("a||b").is_err());
+    assert!(Pattern::new("|").is_err());
+    assert!(Pattern::new("*").is_err());
+    assert!(Pattern::new("a**").is_err());
+    assert!(Pattern::new("(a)").is_err());
+    assert!(Pattern::new("[ab").is_err());
+    assert!(Pattern::new("[z-a]").is_err());
+    assert!(Pattern::new("a\\").is_err());
+    assert!(Pattern::new("\\(").is_ok());
+}